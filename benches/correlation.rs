@@ -0,0 +1,149 @@
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use kobo_db_tools::Parser;
+use rusqlite::{params, Connection};
+use std::str::FromStr;
+use std::time::Instant;
+
+const EVENT_COUNT: usize = 100_000;
+const ITERATIONS: u32 = 5;
+
+fn format_time(ts: DateTime<Utc>) -> String {
+    ts.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+fn build_synthetic_db() -> Connection {
+    let mut conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch(
+        "CREATE TABLE AnalyticsEvents (
+            Id TEXT PRIMARY KEY,
+            Type TEXT NOT NULL,
+            Timestamp TEXT NOT NULL,
+            Attributes TEXT,
+            Metrics TEXT
+        );
+        CREATE TABLE content (
+            ContentID TEXT PRIMARY KEY,
+            ContentType INTEGER,
+            Title TEXT,
+            Attribution TEXT,
+            BookID TEXT,
+            VolumeIndex INTEGER,
+            ISBN TEXT,
+            Series TEXT,
+            SeriesNumber TEXT,
+            ReadStatus INTEGER
+        );",
+    )
+    .unwrap();
+
+    let tx = conn.transaction().unwrap();
+    {
+        let mut insert = tx
+            .prepare(
+                "INSERT INTO AnalyticsEvents (Id, Type, Timestamp, Attributes, Metrics) VALUES (?1, ?2, ?3, ?4, ?5)",
+            )
+            .unwrap();
+        let mut ts = DateTime::<Utc>::from_str("2020-01-01T08:00:00Z").unwrap();
+        let mut written = 0;
+        let mut session = 0;
+        while written < EVENT_COUNT {
+            let volume = format!("book{}", session % 50);
+            let progress = session % 100;
+            insert
+                .execute(params![
+                    format!("open{}", session),
+                    "OpenContent",
+                    format_time(ts),
+                    format!(
+                        "{{\"progress\":\"{}\",\"volumeid\":\"{}\"}}",
+                        progress, volume
+                    ),
+                    "",
+                ])
+                .unwrap();
+            for step in 0..6 {
+                let at = ts + Duration::minutes(2 + step * 3);
+                let (kind, attributes, metrics) = match step % 3 {
+                    0 => (
+                        "DictionaryLookup",
+                        "{\"Dictionary\":\"en\",\"Word\":\"word\"}".to_string(),
+                        String::new(),
+                    ),
+                    1 => (
+                        "BrightnessAdjusted",
+                        "{\"Method\":\"manual\"}".to_string(),
+                        format!("{{\"NewBrightness\":{}}}", 10 + step * 10),
+                    ),
+                    _ => (
+                        "NaturalLightAdjusted",
+                        "{\"Method\":\"auto\"}".to_string(),
+                        format!("{{\"NewNaturalLight\":{}}}", 20 + step * 5),
+                    ),
+                };
+                insert
+                    .execute(params![
+                        format!("event{}_{}", session, step),
+                        kind,
+                        format_time(at),
+                        attributes,
+                        metrics,
+                    ])
+                    .unwrap();
+            }
+            insert
+                .execute(params![
+                    format!("leave{}", session),
+                    "LeaveContent",
+                    format_time(ts + Duration::minutes(20)),
+                    format!(
+                        "{{\"progress\":\"{}\",\"volumeid\":\"{}\"}}",
+                        progress, volume
+                    ),
+                    "{\"ButtonPressCount\":3,\"SecondsRead\":1200,\"PagesTurned\":12}",
+                ])
+                .unwrap();
+            let kind = if session % 10 == 0 {
+                "PluggedIn"
+            } else {
+                "AppStart"
+            };
+            insert
+                .execute(params![
+                    format!("app{}", session),
+                    kind,
+                    format_time(ts + Duration::hours(2)),
+                    "{\"BatteryLevel\":\"80\"}",
+                    "",
+                ])
+                .unwrap();
+            written += 9;
+            session += 1;
+            ts += Duration::hours(6);
+        }
+    }
+    tx.commit().unwrap();
+    conn
+}
+
+fn main() {
+    let conn = build_synthetic_db();
+    let mut best = None;
+    let mut sessions = 0;
+    let mut cycles = 0;
+    for _ in 0..ITERATIONS {
+        let started = Instant::now();
+        let analysis = Parser::parse_correlated(&conn).unwrap();
+        let elapsed = started.elapsed();
+        sessions = analysis.sessions.len();
+        cycles = analysis.cycles.len();
+        best = Some(best.map_or(elapsed, |current: std::time::Duration| current.min(elapsed)));
+    }
+    println!(
+        "parse_correlated over {} events: {} sessions, {} cycles, best of {}: {:?}",
+        EVENT_COUNT,
+        sessions,
+        cycles,
+        ITERATIONS,
+        best.unwrap_or_default()
+    );
+}
@@ -30,6 +30,7 @@ fn main() -> Result<(), Box<dyn Error>> {
             session.brightness.len() + session.natural_light.len()
         );
         println!("  app events: {}", session.app_events.len());
+        println!("  annotations: {}", session.annotations.len());
     }
 
     println!(
@@ -51,7 +52,7 @@ fn main() -> Result<(), Box<dyn Error>> {
             index + 1,
             cycle.start.to_rfc3339(),
             end,
-            cycle.sessions.len(),
+            cycle.session_indices.len(),
             cycle.metrics.app_starts
         );
     }
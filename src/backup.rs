@@ -0,0 +1,399 @@
+use chrono::{DateTime, Utc};
+use rusqlite::types::Value;
+use rusqlite::{params_from_iter, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+pub const BACKUP_FORMAT_VERSION: u32 = 1;
+pub const ANALYTICS_EVENTS_TABLE: &str = "AnalyticsEvents";
+pub const OPTIONAL_BACKUP_TABLES: [&str; 3] = ["Event", "Bookmark", "WordList"];
+
+const MANIFEST_TABLE: &str = "kobo_backup_manifest";
+
+#[derive(Debug, Error)]
+pub enum BackupError {
+    #[error("sqlite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error("manifest error: {0}")]
+    Manifest(#[from] serde_json::Error),
+    #[error("backup archive already exists: {0}")]
+    ArchiveExists(PathBuf),
+    #[error("table not found: {0}")]
+    MissingTable(String),
+    #[error("backup archive has no manifest")]
+    MissingManifest,
+    #[error("unsupported backup format version: {0}")]
+    UnsupportedVersion(u32),
+    #[error("table {table} does not match manifest: expected {expected_rows} rows ({expected_hash}), found {actual_rows} rows ({actual_hash})")]
+    Mismatch {
+        table: String,
+        expected_rows: u64,
+        actual_rows: u64,
+        expected_hash: String,
+        actual_hash: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TableManifest {
+    pub name: String,
+    pub row_count: u64,
+    pub sha256: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub format_version: u32,
+    pub created_at: DateTime<Utc>,
+    pub tables: Vec<TableManifest>,
+}
+
+impl BackupManifest {
+    pub fn table(&self, name: &str) -> Option<&TableManifest> {
+        self.tables.iter().find(|table| table.name == name)
+    }
+}
+
+fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+fn table_sql(db: &Connection, table: &str) -> Result<String, BackupError> {
+    db.query_row(
+        "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = ?1",
+        [table],
+        |row| row.get(0),
+    )
+    .optional()?
+    .ok_or_else(|| BackupError::MissingTable(table.to_string()))
+}
+
+fn column_names(db: &Connection, table: &str) -> Result<Vec<String>, BackupError> {
+    let stmt = db.prepare(&format!("SELECT * FROM {}", quote(table)))?;
+    Ok(stmt.column_names().into_iter().map(String::from).collect())
+}
+
+fn read_rows(
+    db: &Connection,
+    table: &str,
+    columns: &[String],
+) -> Result<Vec<Vec<Value>>, BackupError> {
+    let select = columns
+        .iter()
+        .map(|column| quote(column))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let mut stmt = db.prepare(&format!("SELECT {} FROM {}", select, quote(table)))?;
+    let rows = stmt.query_map([], |row| {
+        (0..columns.len())
+            .map(|index| row.get::<_, Value>(index))
+            .collect::<rusqlite::Result<Vec<Value>>>()
+    })?;
+    Ok(rows.collect::<rusqlite::Result<_>>()?)
+}
+
+fn hash_row(hasher: &mut Sha256, row: &[Value]) {
+    for value in row {
+        match value {
+            Value::Null => hasher.update([0u8]),
+            Value::Integer(number) => {
+                hasher.update([1u8]);
+                hasher.update(number.to_le_bytes());
+            }
+            Value::Real(number) => {
+                hasher.update([2u8]);
+                hasher.update(number.to_le_bytes());
+            }
+            Value::Text(text) => {
+                hasher.update([3u8]);
+                hasher.update((text.len() as u64).to_le_bytes());
+                hasher.update(text.as_bytes());
+            }
+            Value::Blob(bytes) => {
+                hasher.update([4u8]);
+                hasher.update((bytes.len() as u64).to_le_bytes());
+                hasher.update(bytes);
+            }
+        }
+    }
+}
+
+pub fn table_manifest(db: &Connection, table: &str) -> Result<TableManifest, BackupError> {
+    table_sql(db, table)?;
+    let mut columns = column_names(db, table)?;
+    columns.sort();
+    let mut row_hashes: Vec<[u8; 32]> = read_rows(db, table, &columns)?
+        .iter()
+        .map(|row| {
+            let mut hasher = Sha256::new();
+            hash_row(&mut hasher, row);
+            hasher.finalize().into()
+        })
+        .collect();
+    row_hashes.sort_unstable();
+
+    let mut hasher = Sha256::new();
+    for column in &columns {
+        hasher.update((column.len() as u64).to_le_bytes());
+        hasher.update(column.as_bytes());
+    }
+    for row_hash in &row_hashes {
+        hasher.update(row_hash);
+    }
+    Ok(TableManifest {
+        name: table.to_string(),
+        row_count: row_hashes.len() as u64,
+        sha256: hex::encode(hasher.finalize()),
+    })
+}
+
+fn copy_rows(
+    source: &Connection,
+    target: &Connection,
+    table: &str,
+    insert: &str,
+) -> Result<usize, BackupError> {
+    let columns = column_names(source, table)?;
+    let sql = format!(
+        "{} INTO {} ({}) VALUES ({})",
+        insert,
+        quote(table),
+        columns
+            .iter()
+            .map(|column| quote(column))
+            .collect::<Vec<_>>()
+            .join(", "),
+        vec!["?"; columns.len()].join(", ")
+    );
+    let mut stmt = target.prepare(&sql)?;
+    let mut inserted = 0;
+    for row in read_rows(source, table, &columns)? {
+        inserted += stmt.execute(params_from_iter(row))?;
+    }
+    Ok(inserted)
+}
+
+fn write_archive(
+    db: &Connection,
+    archive: &Path,
+    tables: &[&str],
+    schemas: &[String],
+) -> Result<BackupManifest, BackupError> {
+    let mut target = Connection::open(archive)?;
+    let tx = target.transaction()?;
+    let mut manifest = BackupManifest {
+        format_version: BACKUP_FORMAT_VERSION,
+        created_at: Utc::now(),
+        tables: Vec::new(),
+    };
+    for (table, sql) in tables.iter().zip(schemas) {
+        tx.execute_batch(sql)?;
+        copy_rows(db, &tx, table, "INSERT")?;
+        let expected = table_manifest(db, table)?;
+        let actual = table_manifest(&tx, table)?;
+        check_table(&expected, &actual)?;
+        manifest.tables.push(expected);
+    }
+    tx.execute_batch(&format!(
+        "CREATE TABLE {} (manifest TEXT NOT NULL)",
+        MANIFEST_TABLE
+    ))?;
+    tx.execute(
+        &format!("INSERT INTO {} (manifest) VALUES (?1)", MANIFEST_TABLE),
+        [serde_json::to_string(&manifest)?],
+    )?;
+    tx.commit()?;
+    Ok(manifest)
+}
+
+pub fn backup_tables<P: AsRef<Path>>(
+    db: &Connection,
+    archive: P,
+    tables: &[&str],
+) -> Result<BackupManifest, BackupError> {
+    let archive = archive.as_ref();
+    if archive.exists() {
+        return Err(BackupError::ArchiveExists(archive.to_path_buf()));
+    }
+    let schemas = tables
+        .iter()
+        .map(|table| table_sql(db, table))
+        .collect::<Result<Vec<_>, _>>()?;
+    let result = write_archive(db, archive, tables, &schemas);
+    if result.is_err() {
+        let _ = std::fs::remove_file(archive);
+    }
+    result
+}
+
+pub fn backup_analytics_events<P: AsRef<Path>>(
+    db: &Connection,
+    archive: P,
+) -> Result<BackupManifest, BackupError> {
+    backup_tables(db, archive, &[ANALYTICS_EVENTS_TABLE])
+}
+
+fn check_table(expected: &TableManifest, actual: &TableManifest) -> Result<(), BackupError> {
+    if expected.row_count != actual.row_count || expected.sha256 != actual.sha256 {
+        return Err(BackupError::Mismatch {
+            table: expected.name.clone(),
+            expected_rows: expected.row_count,
+            actual_rows: actual.row_count,
+            expected_hash: expected.sha256.clone(),
+            actual_hash: actual.sha256.clone(),
+        });
+    }
+    Ok(())
+}
+
+fn read_manifest(archive: &Connection) -> Result<BackupManifest, BackupError> {
+    table_sql(archive, MANIFEST_TABLE).map_err(|_| BackupError::MissingManifest)?;
+    let json: String = archive
+        .query_row(
+            &format!("SELECT manifest FROM {} LIMIT 1", MANIFEST_TABLE),
+            [],
+            |row| row.get(0),
+        )
+        .optional()?
+        .ok_or(BackupError::MissingManifest)?;
+    let manifest: BackupManifest = serde_json::from_str(&json)?;
+    if manifest.format_version != BACKUP_FORMAT_VERSION {
+        return Err(BackupError::UnsupportedVersion(manifest.format_version));
+    }
+    Ok(manifest)
+}
+
+fn verify_archive(archive: &Connection) -> Result<BackupManifest, BackupError> {
+    let manifest = read_manifest(archive)?;
+    for expected in &manifest.tables {
+        check_table(expected, &table_manifest(archive, &expected.name)?)?;
+    }
+    Ok(manifest)
+}
+
+pub fn verify_backup<P: AsRef<Path>>(archive: P) -> Result<BackupManifest, BackupError> {
+    let archive = Connection::open_with_flags(
+        archive,
+        rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )?;
+    verify_archive(&archive)
+}
+
+pub fn restore_backup<P: AsRef<Path>>(
+    archive: P,
+    db: &mut Connection,
+) -> Result<HashMap<String, usize>, BackupError> {
+    let archive = Connection::open_with_flags(
+        archive,
+        rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )?;
+    let manifest = verify_archive(&archive)?;
+
+    let tx = db.transaction()?;
+    let mut restored = HashMap::new();
+    for table in &manifest.tables {
+        if table_sql(&tx, &table.name).is_err() {
+            tx.execute_batch(&table_sql(&archive, &table.name)?)?;
+        }
+        let inserted = copy_rows(&archive, &tx, &table.name, "INSERT OR IGNORE")?;
+        restored.insert(table.name.clone(), inserted);
+    }
+    tx.commit()?;
+    Ok(restored)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn setup_test_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE AnalyticsEvents (
+                Id TEXT PRIMARY KEY,
+                Type TEXT NOT NULL,
+                Timestamp TEXT NOT NULL,
+                Attributes TEXT,
+                Metrics TEXT
+            );
+            CREATE TABLE WordList (
+                Text TEXT PRIMARY KEY,
+                VolumeId TEXT,
+                DictSuffix TEXT,
+                DateCreated TEXT
+            );
+            INSERT INTO AnalyticsEvents VALUES
+                ('1', 'OpenContent', '2023-01-01T10:00:00Z', '{\"progress\":\"10\"}', '{}'),
+                ('2', 'LeaveContent', '2023-01-01T10:30:00Z', '{\"progress\":\"20\"}', '{\"SecondsRead\":1800}'),
+                ('3', 'AppStart', '2023-01-01T09:00:00Z', NULL, NULL);
+            INSERT INTO WordList VALUES ('spice', 'vol1', '-en', '2023-01-01T10:10:00Z');",
+        )
+        .unwrap();
+        conn
+    }
+
+    fn archive_path() -> PathBuf {
+        std::env::temp_dir().join(format!("kobo-backup-{}.sqlite", Uuid::new_v4()))
+    }
+
+    #[test]
+    fn test_backup_verify_and_restore() {
+        let mut db = setup_test_db();
+        let path = archive_path();
+        let manifest = backup_tables(&db, &path, &[ANALYTICS_EVENTS_TABLE, "WordList"]).unwrap();
+        assert_eq!(manifest.table(ANALYTICS_EVENTS_TABLE).unwrap().row_count, 3);
+        assert_eq!(manifest.table("WordList").unwrap().row_count, 1);
+        assert_eq!(verify_backup(&path).unwrap(), manifest);
+        assert!(matches!(
+            backup_analytics_events(&db, &path),
+            Err(BackupError::ArchiveExists(_))
+        ));
+
+        db.execute("DELETE FROM AnalyticsEvents WHERE Id != '1'", [])
+            .unwrap();
+        db.execute("DROP TABLE WordList", []).unwrap();
+        let restored = restore_backup(&path, &mut db).unwrap();
+        assert_eq!(restored[ANALYTICS_EVENTS_TABLE], 2);
+        assert_eq!(restored["WordList"], 1);
+        assert_eq!(
+            table_manifest(&db, ANALYTICS_EVENTS_TABLE).unwrap(),
+            *manifest.table(ANALYTICS_EVENTS_TABLE).unwrap()
+        );
+
+        let restored = restore_backup(&path, &mut db).unwrap();
+        assert_eq!(restored[ANALYTICS_EVENTS_TABLE], 0);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_verify_detects_tampering() {
+        let db = setup_test_db();
+        let path = archive_path();
+        backup_analytics_events(&db, &path).unwrap();
+        Connection::open(&path)
+            .unwrap()
+            .execute(
+                "UPDATE AnalyticsEvents SET Type = 'AppStart' WHERE Id = '1'",
+                [],
+            )
+            .unwrap();
+        let err = verify_backup(&path).unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        assert!(
+            matches!(err, BackupError::Mismatch { table, .. } if table == ANALYTICS_EVENTS_TABLE)
+        );
+    }
+
+    #[test]
+    fn test_backup_missing_table() {
+        let db = setup_test_db();
+        let path = archive_path();
+        let err = backup_tables(&db, &path, &["Event"]).unwrap_err();
+        assert!(matches!(err, BackupError::MissingTable(table) if table == "Event"));
+        assert!(!path.exists());
+    }
+}
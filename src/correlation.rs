@@ -0,0 +1,276 @@
+use chrono::{DateTime, Duration, Utc};
+use std::ops::Range;
+
+use crate::{AppEventKind, CorrelatedSession};
+
+const DEFAULT_TOLERANCE_SECONDS: i64 = 30;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MatchStrategy {
+    #[default]
+    FirstMatch,
+    Nearest,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CorrelationConfig {
+    pub dictionary_tolerance: Duration,
+    pub brightness_tolerance: Duration,
+    pub natural_light_tolerance: Duration,
+    pub app_event_tolerance: Duration,
+    pub bookmark_tolerance: Duration,
+    pub strategy: MatchStrategy,
+    pub attach_app_starts: bool,
+    pub attach_plugged_in: bool,
+    pub attach_bookmarks: bool,
+}
+
+impl Default for CorrelationConfig {
+    fn default() -> Self {
+        let tolerance = Duration::seconds(DEFAULT_TOLERANCE_SECONDS);
+        Self {
+            dictionary_tolerance: tolerance,
+            brightness_tolerance: tolerance,
+            natural_light_tolerance: tolerance,
+            app_event_tolerance: tolerance,
+            bookmark_tolerance: tolerance,
+            strategy: MatchStrategy::FirstMatch,
+            attach_app_starts: true,
+            attach_plugged_in: true,
+            attach_bookmarks: true,
+        }
+    }
+}
+
+impl CorrelationConfig {
+    pub fn with_tolerance(mut self, tolerance: Duration) -> Self {
+        self.dictionary_tolerance = tolerance;
+        self.brightness_tolerance = tolerance;
+        self.natural_light_tolerance = tolerance;
+        self.app_event_tolerance = tolerance;
+        self.bookmark_tolerance = tolerance;
+        self
+    }
+
+    pub fn with_strategy(mut self, strategy: MatchStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    pub fn attaches_app_event(&self, kind: AppEventKind) -> bool {
+        match kind {
+            AppEventKind::AppStart => self.attach_app_starts,
+            AppEventKind::PluggedIn => self.attach_plugged_in,
+        }
+    }
+}
+
+pub struct SessionIndex {
+    starts: Vec<DateTime<Utc>>,
+    ends: Vec<Option<DateTime<Utc>>>,
+    max_ends: Vec<DateTime<Utc>>,
+}
+
+impl SessionIndex {
+    pub fn new(sessions: &[CorrelatedSession]) -> Self {
+        let starts = sessions
+            .iter()
+            .map(|session| session.session.time_start)
+            .collect();
+        let mut max_end = DateTime::<Utc>::MIN_UTC;
+        let max_ends = sessions
+            .iter()
+            .map(|session| {
+                if let Some(end) = session.session.time_end {
+                    max_end = max_end.max(end);
+                }
+                max_end
+            })
+            .collect();
+        let ends = sessions
+            .iter()
+            .map(|session| session.session.time_end)
+            .collect();
+        Self {
+            starts,
+            ends,
+            max_ends,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.starts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.starts.is_empty()
+    }
+
+    pub fn find(&self, ts: DateTime<Utc>, tolerance: Duration) -> Option<usize> {
+        let (first, upper) = self.candidates(ts, tolerance);
+        (first < upper).then_some(first)
+    }
+
+    pub fn find_with(
+        &self,
+        ts: DateTime<Utc>,
+        tolerance: Duration,
+        strategy: MatchStrategy,
+    ) -> Option<usize> {
+        match strategy {
+            MatchStrategy::FirstMatch => self.find(ts, tolerance),
+            MatchStrategy::Nearest => self.find_nearest(ts, tolerance),
+        }
+    }
+
+    pub fn find_nearest(&self, ts: DateTime<Utc>, tolerance: Duration) -> Option<usize> {
+        self.find_where(ts, tolerance, MatchStrategy::Nearest, |_| true)
+    }
+
+    pub fn find_where(
+        &self,
+        ts: DateTime<Utc>,
+        tolerance: Duration,
+        strategy: MatchStrategy,
+        mut accept: impl FnMut(usize) -> bool,
+    ) -> Option<usize> {
+        let (first, upper) = self.candidates(ts, tolerance);
+        let mut matches = (first..upper).filter_map(|index| {
+            let start = self.starts[index];
+            let end = self.ends[index]?;
+            if ts < start - tolerance || ts > end + tolerance || !accept(index) {
+                return None;
+            }
+            let distance = if ts < start {
+                start - ts
+            } else if ts > end {
+                ts - end
+            } else {
+                Duration::zero()
+            };
+            Some((distance, index))
+        });
+        match strategy {
+            MatchStrategy::FirstMatch => matches.next(),
+            MatchStrategy::Nearest => matches.min(),
+        }
+        .map(|(_, index)| index)
+    }
+
+    fn candidates(&self, ts: DateTime<Utc>, tolerance: Duration) -> (usize, usize) {
+        let upper = self
+            .starts
+            .partition_point(|start| *start - tolerance <= ts);
+        let first = self.max_ends.partition_point(|end| *end + tolerance < ts);
+        (first, upper)
+    }
+
+    pub fn starting_in(&self, start: DateTime<Utc>, end: Option<DateTime<Utc>>) -> Range<usize> {
+        let lower = self.starts.partition_point(|ts| *ts < start);
+        let upper = match end {
+            Some(end) => self.starts.partition_point(|ts| *ts < end),
+            None => self.starts.len(),
+        };
+        lower..upper.max(lower)
+    }
+}
+
+pub fn sort_sessions(sessions: &mut [CorrelatedSession]) {
+    sessions.sort_by_key(|session| session.session.time_start);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ReadingSession;
+    use std::str::FromStr;
+
+    fn ts(value: &str) -> DateTime<Utc> {
+        DateTime::<Utc>::from_str(value).unwrap()
+    }
+
+    fn session(start: &str, end: &str) -> CorrelatedSession {
+        let mut session = ReadingSession::new(ts(start), 0, None, None, "open".to_string());
+        session
+            .complete_session(ts(end), 10, 0, 60, 1, "leave".to_string())
+            .unwrap();
+        CorrelatedSession::new(session)
+    }
+
+    fn linear_find(
+        sessions: &[CorrelatedSession],
+        at: DateTime<Utc>,
+        tolerance: Duration,
+    ) -> Option<usize> {
+        sessions.iter().position(|session| {
+            session.session.time_end.is_some_and(|end| {
+                at >= session.session.time_start - tolerance && at <= end + tolerance
+            })
+        })
+    }
+
+    #[test]
+    fn test_find_matches_linear_first_match() {
+        let sessions = vec![
+            session("2023-01-01T10:00:00Z", "2023-01-01T11:00:00Z"),
+            session("2023-01-01T10:30:00Z", "2023-01-01T10:40:00Z"),
+            session("2023-01-01T12:00:00Z", "2023-01-01T12:10:00Z"),
+            session("2023-01-01T12:05:00Z", "2023-01-01T13:00:00Z"),
+        ];
+        let index = SessionIndex::new(&sessions);
+        let tolerance = Duration::seconds(30);
+        let mut at = ts("2023-01-01T09:50:00Z");
+        while at < ts("2023-01-01T13:10:00Z") {
+            assert_eq!(
+                index.find(at, tolerance),
+                linear_find(&sessions, at, tolerance),
+                "mismatch at {}",
+                at
+            );
+            at += Duration::seconds(15);
+        }
+    }
+
+    #[test]
+    fn test_find_nearest_prefers_closest_window() {
+        let sessions = vec![
+            session("2023-01-01T10:00:00Z", "2023-01-01T10:10:00Z"),
+            session("2023-01-01T10:10:20Z", "2023-01-01T10:20:00Z"),
+        ];
+        let index = SessionIndex::new(&sessions);
+        let tolerance = Duration::seconds(30);
+        let at = ts("2023-01-01T10:10:15Z");
+        assert_eq!(
+            index.find_with(at, tolerance, MatchStrategy::FirstMatch),
+            Some(0)
+        );
+        assert_eq!(
+            index.find_with(at, tolerance, MatchStrategy::Nearest),
+            Some(1)
+        );
+        assert_eq!(
+            index.find_nearest(ts("2023-01-01T11:00:00Z"), tolerance),
+            None
+        );
+        assert_eq!(
+            index.find_where(at, tolerance, MatchStrategy::FirstMatch, |i| i != 0),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn test_starting_in() {
+        let sessions = vec![
+            session("2023-01-01T10:00:00Z", "2023-01-01T11:00:00Z"),
+            session("2023-01-01T12:00:00Z", "2023-01-01T12:10:00Z"),
+            session("2023-01-02T12:00:00Z", "2023-01-02T12:10:00Z"),
+        ];
+        let index = SessionIndex::new(&sessions);
+        assert_eq!(
+            index.starting_in(ts("2023-01-01T11:00:00Z"), Some(ts("2023-01-02T00:00:00Z"))),
+            1..2
+        );
+        assert_eq!(index.starting_in(ts("2023-01-01T00:00:00Z"), None), 0..3);
+        assert_eq!(index.len(), 3);
+    }
+}
@@ -20,7 +20,7 @@ fn trigger_exists(conn: &Connection) -> rusqlite::Result<bool> {
     Ok(rows.next()?.is_some())
 }
 
-pub fn install_analytics_events_trigger(conn: &Connection) -> Result<(), TriggerError> {
+pub(crate) fn install_analytics_events_trigger(conn: &Connection) -> Result<(), TriggerError> {
     if trigger_exists(conn)? {
         return Err(TriggerError::AlreadyExists(TRIGGER_NAME));
     }
@@ -34,7 +34,7 @@ pub fn install_analytics_events_trigger(conn: &Connection) -> Result<(), Trigger
     Ok(())
 }
 
-pub fn remove_analytics_events_trigger(conn: &Connection) -> Result<(), TriggerError> {
+pub(crate) fn remove_analytics_events_trigger(conn: &Connection) -> Result<(), TriggerError> {
     if !trigger_exists(conn)? {
         return Err(TriggerError::NotFound(TRIGGER_NAME));
     }
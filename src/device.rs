@@ -0,0 +1,297 @@
+use chrono::Utc;
+use rusqlite::{Connection, OpenFlags};
+use std::ffi::OsString;
+use std::fs::{File, OpenOptions};
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+use crate::db::{install_analytics_events_trigger, remove_analytics_events_trigger, TriggerError};
+use crate::schema::{DbSchema, FeatureReport};
+
+pub const KOBO_REQUIRED_TABLES: [&str; 3] = ["DbVersion", "content", "AnalyticsEvents"];
+
+const SIDECAR_SUFFIXES: [&str; 2] = ["-wal", "-journal"];
+
+#[derive(Debug, Error)]
+pub enum DeviceDbError {
+    #[error("sqlite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("not a Kobo database, missing tables: {}", .0.join(", "))]
+    NotKoboDatabase(Vec<String>),
+    #[error("refusing to write while {0} exists")]
+    JournalPresent(PathBuf),
+}
+
+fn sidecar(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+fn create_backup(path: &Path, backup_dir: &Path) -> io::Result<PathBuf> {
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let stamp = Utc::now().format("%Y%m%dT%H%M%S%3fZ");
+    let mut source = File::open(path)?;
+    for attempt in 0u32.. {
+        let backup = match attempt {
+            0 => backup_dir.join(format!("{file_name}.{stamp}.bak")),
+            n => backup_dir.join(format!("{file_name}.{stamp}-{n}.bak")),
+        };
+        let mut target = match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&backup)
+        {
+            Ok(target) => target,
+            Err(err) if err.kind() == ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err),
+        };
+        io::copy(&mut source, &mut target)?;
+        target.sync_all()?;
+        return Ok(backup);
+    }
+    unreachable!("backup attempts exhausted")
+}
+
+fn missing_tables(conn: &Connection) -> rusqlite::Result<Vec<String>> {
+    let mut stmt =
+        conn.prepare("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1 LIMIT 1")?;
+    let mut missing = Vec::new();
+    for table in KOBO_REQUIRED_TABLES {
+        if !stmt.exists([table])? {
+            missing.push(table.to_string());
+        }
+    }
+    Ok(missing)
+}
+
+#[derive(Debug)]
+pub struct DeviceDb {
+    conn: Connection,
+    path: PathBuf,
+}
+
+impl DeviceDb {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, DeviceDbError> {
+        let path = path.as_ref().to_path_buf();
+        let conn = Connection::open_with_flags(
+            &path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+        Ok(Self { conn, path })
+    }
+
+    pub fn open_immutable<P: AsRef<Path>>(path: P) -> Result<Self, DeviceDbError> {
+        let path = path.as_ref().to_path_buf();
+        let uri = format!(
+            "file:{}?immutable=1",
+            path.to_string_lossy()
+                .replace('%', "%25")
+                .replace('?', "%3f")
+                .replace('#', "%23")
+        );
+        let conn = Connection::open_with_flags(
+            uri,
+            OpenFlags::SQLITE_OPEN_READ_ONLY
+                | OpenFlags::SQLITE_OPEN_URI
+                | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+        Ok(Self { conn, path })
+    }
+
+    pub fn connection(&self) -> &Connection {
+        &self.conn
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn feature_report(&self) -> Result<FeatureReport, DeviceDbError> {
+        Ok(DbSchema::detect(&self.conn)?.features())
+    }
+
+    pub fn check_schema(&self) -> Result<(), DeviceDbError> {
+        let missing = missing_tables(&self.conn)?;
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(DeviceDbError::NotKoboDatabase(missing))
+        }
+    }
+
+    pub fn writable(&self) -> Result<WritableDeviceDb, DeviceDbError> {
+        let dir = self
+            .path
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        self.writable_with_backup_dir(dir)
+    }
+
+    pub fn writable_with_backup_dir<P: AsRef<Path>>(
+        &self,
+        backup_dir: P,
+    ) -> Result<WritableDeviceDb, DeviceDbError> {
+        self.check_schema()?;
+
+        for suffix in SIDECAR_SUFFIXES {
+            let journal = sidecar(&self.path, suffix);
+            if journal.exists() {
+                return Err(DeviceDbError::JournalPresent(journal));
+            }
+        }
+
+        let backup = create_backup(&self.path, backup_dir.as_ref())?;
+        let conn = Connection::open_with_flags(
+            &self.path,
+            OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+        Ok(WritableDeviceDb {
+            conn,
+            path: self.path.clone(),
+            backup,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct WritableDeviceDb {
+    conn: Connection,
+    path: PathBuf,
+    backup: PathBuf,
+}
+
+impl WritableDeviceDb {
+    pub fn connection(&self) -> &Connection {
+        &self.conn
+    }
+
+    pub fn connection_mut(&mut self) -> &mut Connection {
+        &mut self.conn
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn backup_path(&self) -> &Path {
+        &self.backup
+    }
+
+    pub fn install_analytics_events_trigger(&self) -> Result<(), TriggerError> {
+        install_analytics_events_trigger(&self.conn)
+    }
+
+    pub fn remove_analytics_events_trigger(&self) -> Result<(), TriggerError> {
+        remove_analytics_events_trigger(&self.conn)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn setup_test_dir(schema: &str) -> (PathBuf, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("kobo-device-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("KoboReader.sqlite");
+        Connection::open(&path)
+            .unwrap()
+            .execute_batch(schema)
+            .unwrap();
+        (dir, path)
+    }
+
+    const KOBO_SCHEMA: &str = "CREATE TABLE DbVersion (version INTEGER);
+        INSERT INTO DbVersion VALUES (174);
+        CREATE TABLE content (ContentID TEXT PRIMARY KEY, Title TEXT);
+        CREATE TABLE AnalyticsEvents (Id TEXT PRIMARY KEY, Type TEXT, Timestamp TEXT);";
+
+    #[test]
+    fn test_read_only_by_default() {
+        let (dir, path) = setup_test_dir(KOBO_SCHEMA);
+        for db in [
+            DeviceDb::open(&path).unwrap(),
+            DeviceDb::open_immutable(&path).unwrap(),
+        ] {
+            db.check_schema().unwrap();
+            assert_eq!(db.feature_report().unwrap().db_version, Some(174));
+            let err = db
+                .connection()
+                .execute("INSERT INTO content VALUES ('a', 'b')", [])
+                .unwrap_err();
+            assert!(err.to_string().contains("readonly"));
+        }
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_writable_backs_up_first() {
+        let (dir, path) = setup_test_dir(KOBO_SCHEMA);
+        let writable = DeviceDb::open(&path).unwrap().writable().unwrap();
+        let backup = writable.backup_path().to_path_buf();
+        assert!(backup
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with("KoboReader.sqlite."));
+        writable.install_analytics_events_trigger().unwrap();
+        drop(writable);
+
+        let triggers = |path: &Path| -> i64 {
+            Connection::open(path)
+                .unwrap()
+                .query_row(
+                    "SELECT COUNT(*) FROM sqlite_master WHERE type = 'trigger'",
+                    [],
+                    |row| row.get(0),
+                )
+                .unwrap()
+        };
+        assert_eq!(triggers(&path), 1);
+        assert_eq!(triggers(&backup), 0);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_refuses_unsafe_writes() {
+        let (dir, path) = setup_test_dir("CREATE TABLE content (ContentID TEXT);");
+        let err = DeviceDb::open(&path).unwrap().writable().unwrap_err();
+        assert!(
+            matches!(err, DeviceDbError::NotKoboDatabase(missing) if missing == ["DbVersion", "AnalyticsEvents"])
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+
+        let (dir, path) = setup_test_dir(KOBO_SCHEMA);
+        std::fs::write(sidecar(&path, "-journal"), b"").unwrap();
+        let db = DeviceDb::open(&path).unwrap();
+        let err = db.writable().unwrap_err();
+        assert!(matches!(err, DeviceDbError::JournalPresent(_)));
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 2);
+        db.check_schema().unwrap();
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_backups_never_overwrite() {
+        let (dir, path) = setup_test_dir(KOBO_SCHEMA);
+        let db = DeviceDb::open(&path).unwrap();
+        let backups: Vec<PathBuf> = (0..3)
+            .map(|_| db.writable().unwrap().backup_path().to_path_buf())
+            .collect();
+        assert_eq!(
+            backups
+                .iter()
+                .collect::<std::collections::HashSet<_>>()
+                .len(),
+            3
+        );
+        assert!(backups.iter().all(|backup| backup.exists()));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,107 @@
+use std::io::Write;
+
+use crate::export::{Export, ExportError};
+use crate::model::CorrelatedAnalysis;
+
+impl Export for CorrelatedAnalysis {
+    fn to_csv(&self) -> Result<String, ExportError> {
+        self.sessions.to_csv()
+    }
+
+    fn to_md(&self) -> Result<String, ExportError> {
+        let mut buffer = Vec::new();
+        writeln!(buffer, "## Sessions\n")?;
+        write!(buffer, "{}", self.sessions.to_md()?)?;
+        writeln!(buffer, "\n## Charge Cycles\n")?;
+        write!(buffer, "{}", self.cycles.to_md()?)?;
+        writeln!(buffer, "\n## Orphan Events\n")?;
+        writeln!(buffer, "| Kind | Count |")?;
+        writeln!(buffer, "|------|-------|")?;
+        let orphans = &self.orphans;
+        for (kind, count) in [
+            ("Dictionary", orphans.dictionary.len()),
+            ("Brightness", orphans.brightness.len()),
+            ("Natural Light", orphans.natural_light.len()),
+            ("App Events", orphans.app_events.len()),
+            ("Annotations", orphans.annotations.len()),
+        ] {
+            writeln!(buffer, "| {} | {} |", kind, count)?;
+        }
+        Ok(String::from_utf8(buffer)?)
+    }
+
+    fn to_json(&self) -> Result<String, ExportError> {
+        serde_json::to_string(self).map_err(ExportError::JsonToString)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::Import;
+    use crate::{
+        ChargeCycle, ChargeCycleMetrics, CorrelatedSession, DictionaryWord, OrphanEvents,
+        ReadingSession,
+    };
+    use chrono::{DateTime, NaiveDate, Utc};
+    use std::collections::HashMap;
+    use std::str::FromStr;
+
+    fn build_analysis() -> CorrelatedAnalysis {
+        let start = DateTime::<Utc>::from_str("2023-01-01T10:00:00Z").unwrap();
+        let end = DateTime::<Utc>::from_str("2023-01-01T10:10:00Z").unwrap();
+        let mut session = ReadingSession::new(start, 10, None, None, "open1".to_string());
+        session
+            .complete_session(end, 20, 3, 600, 5, "leave1".to_string())
+            .unwrap();
+        let mut correlated = CorrelatedSession::new(session);
+        correlated.dictionary.push(DictionaryWord::new(
+            "test".to_string(),
+            "en".to_string(),
+            Some(correlated.session.id),
+        ));
+        let mut orphans = OrphanEvents::default();
+        orphans.dictionary.push(DictionaryWord::new(
+            "orphan".to_string(),
+            "en".to_string(),
+            None,
+        ));
+        CorrelatedAnalysis {
+            sessions: vec![correlated],
+            orphans,
+            cycles: vec![ChargeCycle {
+                start,
+                end: None,
+                session_indices: vec![0],
+                app_events: Vec::new(),
+                metrics: ChargeCycleMetrics::default(),
+            }],
+            app_start_counts_by_day: HashMap::from([
+                (NaiveDate::from_ymd_opt(2023, 1, 2).unwrap(), 1),
+                (NaiveDate::from_ymd_opt(2023, 1, 1).unwrap(), 3),
+            ]),
+        }
+    }
+
+    #[test]
+    fn test_analysis_to_json_is_nested() {
+        let analysis = build_analysis();
+        let json = analysis.to_json().unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["sessions"][0]["dictionary"][0]["term"], "test");
+        assert_eq!(value["orphans"]["dictionary"][0]["term"], "orphan");
+        assert_eq!(value["cycles"][0]["session_indices"][0], 0);
+        assert!(json.contains("\"app_start_counts_by_day\":{\"2023-01-01\":3,\"2023-01-02\":1}"));
+
+        let imported = CorrelatedAnalysis::from_json(&json).unwrap();
+        assert_eq!(imported.to_json().unwrap(), json);
+    }
+
+    #[test]
+    fn test_analysis_to_md() {
+        let md = build_analysis().to_md().unwrap();
+        assert!(md.starts_with("## Sessions\n"));
+        assert!(md.contains("## Charge Cycles"));
+        assert!(md.contains("| Dictionary | 1 |"));
+    }
+}
@@ -0,0 +1,485 @@
+use chrono::{DateTime, Duration, DurationRound, SecondsFormat, Utc};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::model::{
+    parse_kobo_date, AppEvent, Book, Bookmark, BrightnessEvent, BrightnessHistory, ChargeCycle,
+    CorrelatedAnalysis, CorrelatedSession, DictionaryWord, NaturalLightHistory, OrphanEvents,
+    ReadingSession, ReadingSessions, StitchedSession,
+};
+use crate::parser::EventAnalysis;
+
+pub const RARE_TITLE_BUCKET: &str = "Other";
+
+#[derive(Debug, Clone)]
+pub struct Anonymizer {
+    namespace: Uuid,
+    time_resolution: Option<Duration>,
+    strip_free_text: bool,
+    min_title_count: Option<usize>,
+    title_counts: HashMap<String, usize>,
+}
+
+impl Anonymizer {
+    pub fn new(salt: &str) -> Self {
+        Self {
+            namespace: Uuid::new_v5(&Uuid::NAMESPACE_OID, salt.as_bytes()),
+            time_resolution: None,
+            strip_free_text: true,
+            min_title_count: None,
+            title_counts: HashMap::new(),
+        }
+    }
+
+    pub fn with_time_resolution(mut self, resolution: Duration) -> Self {
+        self.time_resolution = Some(resolution);
+        self
+    }
+
+    pub fn keep_free_text(mut self) -> Self {
+        self.strip_free_text = false;
+        self
+    }
+
+    pub fn with_k_anonymity(mut self, k: usize) -> Self {
+        self.min_title_count = Some(k.max(1));
+        self
+    }
+
+    pub fn observe<'a>(&mut self, sessions: impl IntoIterator<Item = &'a ReadingSession>) {
+        for title in sessions
+            .into_iter()
+            .filter_map(|session| session.book_title.as_deref())
+        {
+            *self.title_counts.entry(title.to_string()).or_insert(0) += 1;
+        }
+    }
+
+    pub fn pseudonym(&self, kind: &str, value: &str) -> String {
+        if value.is_empty() {
+            return String::new();
+        }
+        let hashed = Uuid::new_v5(&self.namespace, format!("{}:{}", kind, value).as_bytes());
+        hashed.simple().to_string()[..16].to_string()
+    }
+
+    pub fn id(&self, id: Uuid) -> Uuid {
+        Uuid::new_v5(&self.namespace, id.as_bytes())
+    }
+
+    pub fn time(&self, ts: DateTime<Utc>) -> DateTime<Utc> {
+        self.time_resolution
+            .and_then(|resolution| ts.duration_trunc(resolution).ok())
+            .unwrap_or(ts)
+    }
+
+    fn date_string(&self, value: &str) -> String {
+        parse_kobo_date(value)
+            .map(|ts| self.time(ts).to_rfc3339_opts(SecondsFormat::Secs, true))
+            .unwrap_or_default()
+    }
+
+    fn keeps_title(&self, title: &str) -> bool {
+        self.min_title_count
+            .is_some_and(|k| self.title_counts.get(title).copied().unwrap_or(0) >= k)
+    }
+
+    pub fn title(&self, title: &str) -> String {
+        if title.is_empty() || self.keeps_title(title) {
+            title.to_string()
+        } else if self.min_title_count.is_some() {
+            RARE_TITLE_BUCKET.to_string()
+        } else {
+            self.pseudonym("title", title)
+        }
+    }
+
+    fn volume(&self, volume_id: &str) -> String {
+        self.pseudonym("volume", volume_id)
+    }
+
+    fn free_text(&self, text: &str) -> String {
+        if self.strip_free_text {
+            String::new()
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+pub trait Anonymize {
+    fn anonymize(&self, anonymizer: &Anonymizer) -> Self;
+}
+
+impl<T: Anonymize> Anonymize for Vec<T> {
+    fn anonymize(&self, anonymizer: &Anonymizer) -> Self {
+        self.iter().map(|item| item.anonymize(anonymizer)).collect()
+    }
+}
+
+impl<T: Anonymize> Anonymize for Option<T> {
+    fn anonymize(&self, anonymizer: &Anonymizer) -> Self {
+        self.as_ref().map(|item| item.anonymize(anonymizer))
+    }
+}
+
+impl Anonymize for ReadingSession {
+    fn anonymize(&self, anonymizer: &Anonymizer) -> Self {
+        Self {
+            id: anonymizer.id(self.id),
+            open_content_id: anonymizer.pseudonym("event", &self.open_content_id),
+            leave_content_id: self
+                .leave_content_id
+                .as_deref()
+                .map(|id| anonymizer.pseudonym("event", id)),
+            time_start: anonymizer.time(self.time_start),
+            time_end: self.time_end.map(|ts| anonymizer.time(ts)),
+            volume_id: self.volume_id.as_deref().map(|id| anonymizer.volume(id)),
+            book_title: self
+                .book_title
+                .as_deref()
+                .map(|title| anonymizer.title(title)),
+            ..self.clone()
+        }
+    }
+}
+
+impl Anonymize for ReadingSessions {
+    fn anonymize(&self, anonymizer: &Anonymizer) -> Self {
+        let mut sessions = ReadingSessions::new();
+        for session in self.get_sessions() {
+            sessions.add_session(session.anonymize(anonymizer));
+        }
+        sessions
+    }
+}
+
+impl Anonymize for StitchedSession {
+    fn anonymize(&self, anonymizer: &Anonymizer) -> Self {
+        Self {
+            session: self.session.anonymize(anonymizer),
+            parts: self.parts.anonymize(anonymizer),
+        }
+    }
+}
+
+impl Anonymize for DictionaryWord {
+    fn anonymize(&self, anonymizer: &Anonymizer) -> Self {
+        DictionaryWord::new(
+            anonymizer.pseudonym("term", self.term()),
+            self.lang().to_string(),
+            self.session_id().map(|id| anonymizer.id(id)),
+        )
+    }
+}
+
+impl Anonymize for BrightnessEvent {
+    fn anonymize(&self, anonymizer: &Anonymizer) -> Self {
+        BrightnessEvent::new(self.brightness.clone(), anonymizer.time(self.timestamp))
+    }
+}
+
+impl Anonymize for BrightnessHistory {
+    fn anonymize(&self, anonymizer: &Anonymizer) -> Self {
+        Self {
+            events: self.events.anonymize(anonymizer),
+        }
+    }
+}
+
+impl Anonymize for NaturalLightHistory {
+    fn anonymize(&self, anonymizer: &Anonymizer) -> Self {
+        Self {
+            events: self.events.anonymize(anonymizer),
+        }
+    }
+}
+
+impl Anonymize for AppEvent {
+    fn anonymize(&self, anonymizer: &Anonymizer) -> Self {
+        let mut attributes = serde_json::Map::new();
+        if let Some(level) = self.battery_level() {
+            attributes.insert("BatteryLevel".to_string(), level.into());
+        }
+        if let Some(charger) = self.charger() {
+            attributes.insert("ChargerType".to_string(), charger.into());
+        }
+        AppEvent::new(
+            self.kind,
+            anonymizer.time(self.timestamp),
+            (!attributes.is_empty()).then_some(serde_json::Value::Object(attributes)),
+        )
+    }
+}
+
+impl Anonymize for Bookmark {
+    fn anonymize(&self, anonymizer: &Anonymizer) -> Self {
+        Self {
+            content_id: anonymizer.pseudonym("content", &self.content_id),
+            content: anonymizer.free_text(&self.content),
+            book_id: anonymizer.volume(&self.book_id),
+            book_title: anonymizer.title(&self.book_title),
+            create_date: anonymizer.date_string(&self.create_date),
+            write_date: anonymizer.date_string(&self.write_date),
+            annotation: self
+                .annotation
+                .as_deref()
+                .map(|text| anonymizer.free_text(text))
+                .filter(|text| !text.is_empty()),
+            ..self.clone()
+        }
+    }
+}
+
+impl Anonymize for Book {
+    fn anonymize(&self, anonymizer: &Anonymizer) -> Self {
+        let authors = if anonymizer.keeps_title(&self.title) {
+            self.authors.clone()
+        } else {
+            anonymizer.pseudonym("author", &self.authors)
+        };
+        Self {
+            authors,
+            title: anonymizer.title(&self.title),
+            book_id: anonymizer.volume(&self.book_id),
+            isbn: None,
+            series: self
+                .series
+                .as_deref()
+                .map(|series| anonymizer.pseudonym("series", series)),
+            shelves: self
+                .shelves
+                .iter()
+                .map(|shelf| anonymizer.pseudonym("shelf", shelf))
+                .collect(),
+            ..self.clone()
+        }
+    }
+}
+
+impl Anonymize for CorrelatedSession {
+    fn anonymize(&self, anonymizer: &Anonymizer) -> Self {
+        Self {
+            session: self.session.anonymize(anonymizer),
+            dictionary: self.dictionary.anonymize(anonymizer),
+            brightness: self.brightness.anonymize(anonymizer),
+            natural_light: self.natural_light.anonymize(anonymizer),
+            app_events: self.app_events.anonymize(anonymizer),
+            annotations: self.annotations.anonymize(anonymizer),
+        }
+    }
+}
+
+impl Anonymize for OrphanEvents {
+    fn anonymize(&self, anonymizer: &Anonymizer) -> Self {
+        Self {
+            dictionary: self.dictionary.anonymize(anonymizer),
+            brightness: self.brightness.anonymize(anonymizer),
+            natural_light: self.natural_light.anonymize(anonymizer),
+            app_events: self.app_events.anonymize(anonymizer),
+            annotations: self.annotations.anonymize(anonymizer),
+        }
+    }
+}
+
+impl Anonymize for ChargeCycle {
+    fn anonymize(&self, anonymizer: &Anonymizer) -> Self {
+        Self {
+            start: anonymizer.time(self.start),
+            end: self.end.map(|ts| anonymizer.time(ts)),
+            app_events: self.app_events.anonymize(anonymizer),
+            ..self.clone()
+        }
+    }
+}
+
+impl Anonymize for CorrelatedAnalysis {
+    fn anonymize(&self, anonymizer: &Anonymizer) -> Self {
+        Self {
+            sessions: self.sessions.anonymize(anonymizer),
+            orphans: self.orphans.anonymize(anonymizer),
+            cycles: self.cycles.anonymize(anonymizer),
+            app_start_counts_by_day: self.app_start_counts_by_day.clone(),
+        }
+    }
+}
+
+impl Anonymize for EventAnalysis {
+    fn anonymize(&self, anonymizer: &Anonymizer) -> Self {
+        let terms = self.terms.as_ref().map(|terms| {
+            let mut anonymized = HashMap::new();
+            for (word, count) in terms {
+                *anonymized.entry(word.anonymize(anonymizer)).or_insert(0) += count;
+            }
+            anonymized
+        });
+        Self {
+            sessions: self.sessions.anonymize(anonymizer),
+            terms,
+            brightness_history: self.brightness_history.anonymize(anonymizer),
+            natural_light_history: self.natural_light_history.anonymize(anonymizer),
+            bookmarks: self.bookmarks.anonymize(anonymizer),
+            books: self.books.anonymize(anonymizer),
+            app_events: self.app_events.anonymize(anonymizer),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::Export;
+    use crate::model::AppEventKind;
+    use std::str::FromStr;
+
+    fn session(title: &str, open: &str, start: &str) -> ReadingSession {
+        let mut session = ReadingSession::new(
+            DateTime::<Utc>::from_str(start).unwrap(),
+            10,
+            Some(title.to_string()),
+            Some(format!("vol-{}", title)),
+            open.to_string(),
+        );
+        session
+            .complete_session(
+                DateTime::<Utc>::from_str(start).unwrap() + Duration::minutes(20),
+                20,
+                0,
+                1200,
+                8,
+                format!("leave-{}", open),
+            )
+            .unwrap();
+        session
+    }
+
+    #[test]
+    fn test_anonymize_correlated_session() {
+        let session = session("Dune", "open-1", "2023-01-01T21:17:42Z");
+        let mut correlated = CorrelatedSession::new(session.clone());
+        correlated.dictionary.push(DictionaryWord::new(
+            "spice".to_string(),
+            "en".to_string(),
+            Some(session.id),
+        ));
+        correlated.app_events.push(AppEvent::new(
+            AppEventKind::PluggedIn,
+            session.time_start,
+            Some(serde_json::json!({
+                "BatteryLevel": "42%",
+                "ChargerType": "USB",
+                "Path": "/mnt/onboard/Dune.epub",
+            })),
+        ));
+        correlated.annotations.push(Bookmark {
+            content_id: "vol-Dune!chapter1".to_string(),
+            content: "Fear is the mind-killer.".to_string(),
+            book_id: "vol-Dune".to_string(),
+            book_title: "Dune".to_string(),
+            color: 0,
+            chapter_progress: 0.5,
+            create_date: "2023-01-01T21:20:13.000".to_string(),
+            write_date: String::new(),
+            annotation: Some("private note".to_string()),
+            chapter_index: Some(1),
+        });
+
+        let anonymizer = Anonymizer::new("secret").with_time_resolution(Duration::hours(1));
+        let anonymized = correlated.anonymize(&anonymizer);
+
+        assert_ne!(anonymized.session.id, session.id);
+        assert_eq!(
+            anonymized.dictionary[0].session_id(),
+            Some(anonymized.session.id)
+        );
+        assert_ne!(anonymized.dictionary[0].term(), "spice");
+        assert_eq!(
+            anonymized.session.volume_id.as_deref(),
+            Some(anonymized.annotations[0].book_id.as_str())
+        );
+        assert_eq!(
+            anonymized.session.book_title.as_deref(),
+            Some(anonymized.annotations[0].book_title.as_str())
+        );
+        assert_ne!(anonymized.annotations[0].book_title, "Dune");
+        assert_eq!(anonymized.annotations[0].content, "");
+        assert_eq!(anonymized.annotations[0].annotation, None);
+        assert_eq!(
+            anonymized.annotations[0].create_date,
+            "2023-01-01T21:00:00Z"
+        );
+        assert_eq!(
+            anonymized.session.time_start,
+            DateTime::<Utc>::from_str("2023-01-01T21:00:00Z").unwrap()
+        );
+        assert_eq!(anonymized.session.seconds_read, Some(1200));
+        assert_eq!(
+            anonymized.app_events[0].timestamp,
+            anonymized.session.time_start
+        );
+        assert_eq!(
+            anonymized.app_events[0].attributes,
+            Some(serde_json::json!({"BatteryLevel": 42, "ChargerType": "USB"}))
+        );
+
+        let again = correlated.anonymize(&Anonymizer::new("secret"));
+        assert_eq!(again.session.id, anonymized.session.id);
+        let other_salt = correlated.anonymize(&Anonymizer::new("other"));
+        assert_ne!(other_salt.session.id, anonymized.session.id);
+
+        let csv = [anonymized].to_csv().unwrap();
+        assert!(!csv.contains("Dune"));
+        assert!(!csv.contains("open-1"));
+    }
+
+    #[test]
+    fn test_anonymize_keeps_short_sessions_linked() {
+        let mut short = session("Dune", "open-1", "2023-01-01T21:00:00Z");
+        short.seconds_read = Some(30);
+        let word = DictionaryWord::new("spice".to_string(), "en".to_string(), Some(short.id));
+        let mut sessions = ReadingSessions::new();
+        sessions.add_session(short);
+        let analysis = EventAnalysis {
+            sessions: Some(sessions),
+            terms: Some(HashMap::from([(word, 1)])),
+            ..Default::default()
+        };
+
+        let anonymized = analysis.anonymize(&Anonymizer::new("secret"));
+        let sessions = anonymized.sessions.unwrap();
+        assert_eq!(sessions.sessions_count(), 1);
+        let (word, _) = anonymized.terms.unwrap().into_iter().next().unwrap();
+        assert_eq!(word.session_id(), Some(sessions.get_sessions()[0].id));
+    }
+
+    #[test]
+    fn test_k_anonymity_buckets_rare_titles() {
+        let mut sessions = ReadingSessions::new();
+        sessions.add_session(session("Dune", "open-1", "2023-01-01T21:00:00Z"));
+        sessions.add_session(session("Dune", "open-2", "2023-01-02T21:00:00Z"));
+        sessions.add_session(session("Obscure Memoir", "open-3", "2023-01-03T21:00:00Z"));
+
+        let mut anonymizer = Anonymizer::new("secret").with_k_anonymity(2);
+        anonymizer.observe(sessions.get_sessions());
+        let anonymized = sessions.anonymize(&anonymizer);
+        let titles: Vec<&str> = anonymized
+            .iter()
+            .filter_map(|session| session.book_title.as_deref())
+            .collect();
+        assert_eq!(titles, ["Dune", "Dune", RARE_TITLE_BUCKET]);
+
+        let book = Book::new(
+            "Anonymous".to_string(),
+            "Obscure Memoir".to_string(),
+            None,
+            "vol-Obscure Memoir".to_string(),
+        )
+        .anonymize(&anonymizer);
+        assert_eq!(book.title, RARE_TITLE_BUCKET);
+        assert_ne!(book.authors, "Anonymous");
+        assert_eq!(
+            Some(book.book_id.as_str()),
+            anonymized.get_sessions()[2].volume_id.as_deref()
+        );
+    }
+}
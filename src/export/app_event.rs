@@ -0,0 +1,97 @@
+use serde::Serialize;
+use std::io::Write;
+
+use crate::export::{format_time, or_na, Export, ExportError};
+use crate::model::AppEvent;
+
+#[derive(Serialize)]
+struct AppEventExportRow {
+    timestamp: String,
+    kind: &'static str,
+    battery_level: Option<u8>,
+    charger: Option<String>,
+    attributes: Option<String>,
+}
+
+fn to_export_row(event: &AppEvent) -> AppEventExportRow {
+    AppEventExportRow {
+        timestamp: format_time(event.timestamp),
+        kind: event.kind.as_str(),
+        battery_level: event.battery_level(),
+        charger: event.charger(),
+        attributes: event.attributes.as_ref().map(|value| value.to_string()),
+    }
+}
+
+impl Export for [AppEvent] {
+    fn to_csv(&self) -> Result<String, ExportError> {
+        let mut wtr = csv::Writer::from_writer(vec![]);
+        for event in self {
+            wtr.serialize(to_export_row(event))?;
+        }
+        Ok(String::from_utf8(wtr.into_inner()?)?)
+    }
+
+    fn to_md(&self) -> Result<String, ExportError> {
+        let mut buffer = Vec::new();
+        writeln!(buffer, "| Timestamp | Event | Battery | Charger |")?;
+        writeln!(buffer, "|-----------|-------|---------|---------|")?;
+        for event in self {
+            let row = to_export_row(event);
+            writeln!(
+                buffer,
+                "| {} | {} | {} | {} |",
+                row.timestamp,
+                row.kind,
+                or_na(row.battery_level.map(|level| format!("{}%", level))),
+                or_na(row.charger)
+            )?;
+        }
+        Ok(String::from_utf8(buffer)?)
+    }
+
+    fn to_json(&self) -> Result<String, ExportError> {
+        serde_json::to_string(self).map_err(ExportError::JsonToString)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AppEventKind;
+    use chrono::{DateTime, Utc};
+    use serde_json::json;
+    use std::str::FromStr;
+
+    fn build_events() -> Vec<AppEvent> {
+        let ts = DateTime::<Utc>::from_str("2023-01-01T10:00:00Z").unwrap();
+        vec![
+            AppEvent::new(
+                AppEventKind::PluggedIn,
+                ts,
+                Some(json!({"BatteryLevel": 42, "ChargerType": "USB"})),
+            ),
+            AppEvent::new(AppEventKind::AppStart, ts, None),
+        ]
+    }
+
+    #[test]
+    fn test_app_events_to_csv() {
+        let csv = build_events().to_csv().unwrap();
+        let expected = [
+            "timestamp,kind,battery_level,charger,attributes",
+            "2023-01-01T10:00:00Z,PluggedIn,42,USB,\"{\"\"BatteryLevel\"\":42,\"\"ChargerType\"\":\"\"USB\"\"}\"",
+            "2023-01-01T10:00:00Z,AppStart,,,",
+            "",
+        ]
+        .join("\n");
+        assert_eq!(csv, expected);
+    }
+
+    #[test]
+    fn test_app_events_to_md() {
+        let md = build_events().to_md().unwrap();
+        assert!(md.contains("| 2023-01-01T10:00:00Z | PluggedIn | 42% | USB |"));
+        assert!(md.contains("| 2023-01-01T10:00:00Z | AppStart | N/A | N/A |"));
+    }
+}
@@ -0,0 +1,90 @@
+use serde::Serialize;
+use std::io::Write;
+
+use crate::export::{or_na, Export, ExportError};
+use crate::model::Book;
+
+#[derive(Serialize)]
+struct BookExportRow<'a> {
+    authors: &'a str,
+    title: &'a str,
+    size: Option<u64>,
+    book_id: &'a str,
+    isbn: Option<&'a str>,
+    series: Option<&'a str>,
+    series_number: Option<&'a str>,
+    read_status: Option<u8>,
+    shelves: String,
+}
+
+fn to_export_row(book: &Book) -> BookExportRow<'_> {
+    BookExportRow {
+        authors: &book.authors,
+        title: &book.title,
+        size: book.size,
+        book_id: &book.book_id,
+        isbn: book.isbn(),
+        series: book.series(),
+        series_number: book.series_number.as_deref(),
+        read_status: book.read_status,
+        shelves: book.shelves.join(";"),
+    }
+}
+
+impl Export for [Book] {
+    fn to_csv(&self) -> Result<String, ExportError> {
+        let mut wtr = csv::Writer::from_writer(vec![]);
+        for book in self {
+            wtr.serialize(to_export_row(book))?;
+        }
+        Ok(String::from_utf8(wtr.into_inner()?)?)
+    }
+
+    fn to_md(&self) -> Result<String, ExportError> {
+        let mut buffer = Vec::new();
+        writeln!(buffer, "| Title | Authors | Size | Book ID |")?;
+        writeln!(buffer, "|-------|---------|------|---------|")?;
+        for book in self {
+            writeln!(
+                buffer,
+                "| {} | {} | {} | {} |",
+                book.title(),
+                book.authors(),
+                or_na(book.size()),
+                book.book_id
+            )?;
+        }
+        Ok(String::from_utf8(buffer)?)
+    }
+
+    fn to_json(&self) -> Result<String, ExportError> {
+        serde_json::to_string(self).map_err(ExportError::JsonToString)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_books_export() {
+        let books = [Book::new(
+            "Author".to_string(),
+            "Title".to_string(),
+            None,
+            "book1".to_string(),
+        )];
+        assert_eq!(
+            books.to_csv().unwrap(),
+            "authors,title,size,book_id,isbn,series,series_number,read_status,shelves\nAuthor,Title,,book1,,,,,\n"
+        );
+        assert!(books
+            .to_md()
+            .unwrap()
+            .contains("| Title | Author | N/A | book1 |"));
+        assert_eq!(
+            books.to_json().unwrap(),
+            "[{\"authors\":\"Author\",\"title\":\"Title\",\"size\":null,\"book_id\":\"book1\",\"isbn\":null,\"series\":null,\"series_number\":null,\"read_status\":null,\"shelves\":[]}]"
+        );
+    }
+}
@@ -55,9 +55,11 @@ mod tests {
             chapter_progress: 0.5,
             create_date: "2025-07-05T10:00:00Z".to_string(),
             write_date: "2025-07-05T10:00:00Z".to_string(),
+            annotation: None,
+            chapter_index: None,
         }];
 
-        let expected_csv = "content_id,content,book_id,book_title,color,chapter_progress,create_date,write_date\ncontent1,content text,book1,Book Title,1,0.5,2025-07-05T10:00:00Z,2025-07-05T10:00:00Z\n".to_string();
+        let expected_csv = "content_id,content,book_id,book_title,color,chapter_progress,create_date,write_date,annotation,chapter_index\ncontent1,content text,book1,Book Title,1,0.5,2025-07-05T10:00:00Z,2025-07-05T10:00:00Z,,\n".to_string();
         let result = bookmarks.to_csv().unwrap();
         assert_eq!(result, expected_csv);
     }
@@ -73,6 +75,8 @@ mod tests {
             chapter_progress: 0.5,
             create_date: "2025-07-05T10:00:00Z".to_string(),
             write_date: "2025-07-05T10:00:00Z".to_string(),
+            annotation: None,
+            chapter_index: None,
         }];
 
         let expected_json = serde_json::to_string(&bookmarks).unwrap();
@@ -0,0 +1,115 @@
+use serde::Serialize;
+use std::io::Write;
+
+use crate::export::{format_time, Export, ExportError};
+use crate::model::{BrightnessEvent, BrightnessHistory, NaturalLightHistory};
+
+#[derive(Serialize)]
+struct BrightnessExportRow<'a> {
+    timestamp: String,
+    method: &'a str,
+    adjustment_method: &'static str,
+    percentage: u8,
+}
+
+fn to_export_row(event: &BrightnessEvent) -> BrightnessExportRow<'_> {
+    BrightnessExportRow {
+        timestamp: format_time(event.timestamp),
+        method: &event.brightness.method,
+        adjustment_method: event.brightness.adjustment_method().as_str(),
+        percentage: event.brightness.percentage,
+    }
+}
+
+impl Export for [BrightnessEvent] {
+    fn to_csv(&self) -> Result<String, ExportError> {
+        let mut wtr = csv::Writer::from_writer(vec![]);
+        for event in self {
+            wtr.serialize(to_export_row(event))?;
+        }
+        Ok(String::from_utf8(wtr.into_inner()?)?)
+    }
+
+    fn to_md(&self) -> Result<String, ExportError> {
+        let mut buffer = Vec::new();
+        writeln!(buffer, "| Timestamp | Method | Adjustment | Percentage |")?;
+        writeln!(buffer, "|-----------|--------|------------|------------|")?;
+        for event in self {
+            let row = to_export_row(event);
+            writeln!(
+                buffer,
+                "| {} | {} | {} | {}% |",
+                row.timestamp, row.method, row.adjustment_method, row.percentage
+            )?;
+        }
+        Ok(String::from_utf8(buffer)?)
+    }
+
+    fn to_json(&self) -> Result<String, ExportError> {
+        let rows: Vec<BrightnessExportRow> = self.iter().map(to_export_row).collect();
+        serde_json::to_string(&rows).map_err(ExportError::JsonToString)
+    }
+}
+
+impl Export for BrightnessHistory {
+    fn to_csv(&self) -> Result<String, ExportError> {
+        self.events.to_csv()
+    }
+
+    fn to_md(&self) -> Result<String, ExportError> {
+        self.events.to_md()
+    }
+
+    fn to_json(&self) -> Result<String, ExportError> {
+        self.events.to_json()
+    }
+}
+
+impl Export for NaturalLightHistory {
+    fn to_csv(&self) -> Result<String, ExportError> {
+        self.events.to_csv()
+    }
+
+    fn to_md(&self) -> Result<String, ExportError> {
+        self.events.to_md()
+    }
+
+    fn to_json(&self) -> Result<String, ExportError> {
+        self.events.to_json()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Brightness;
+    use chrono::{DateTime, Utc};
+    use std::str::FromStr;
+
+    fn build_history() -> BrightnessHistory {
+        let mut history = BrightnessHistory::new();
+        history.insert(BrightnessEvent::new(
+            Brightness::new("Manual".to_string(), 40),
+            DateTime::<Utc>::from_str("2023-01-01T10:00:00Z").unwrap(),
+        ));
+        history
+    }
+
+    #[test]
+    fn test_brightness_to_csv() {
+        let expected =
+            "timestamp,method,adjustment_method,percentage\n2023-01-01T10:00:00Z,Manual,manual,40\n";
+        assert_eq!(build_history().to_csv().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_natural_light_to_md_and_json() {
+        let natural_light = NaturalLightHistory {
+            events: build_history().events,
+        };
+        let md = natural_light.to_md().unwrap();
+        assert!(md.contains("| 2023-01-01T10:00:00Z | Manual | manual | 40% |"));
+        let json = natural_light.to_json().unwrap();
+        assert!(json.contains("\"adjustment_method\":\"manual\""));
+    }
+}
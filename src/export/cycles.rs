@@ -0,0 +1,156 @@
+use serde::Serialize;
+use std::io::Write;
+
+use crate::export::{format_time, or_na, Export, ExportError};
+use crate::model::ChargeCycle;
+
+#[derive(Serialize)]
+struct CycleExportRow {
+    start_time: String,
+    end_time: Option<String>,
+    sessions: usize,
+    app_events: usize,
+    total_seconds_read: u64,
+    total_pages: u64,
+    total_button_presses: u64,
+    dictionary_lookups: usize,
+    brightness_events: usize,
+    app_starts: usize,
+    annotations: usize,
+    start_battery: Option<u8>,
+    end_battery: Option<u8>,
+    charger: Option<String>,
+    battery_drain: Option<u8>,
+    drain_per_reading_hour: Option<f64>,
+    average_brightness: Option<f64>,
+    projected_reading_hours: Option<f64>,
+}
+
+fn to_export_row(cycle: &ChargeCycle) -> CycleExportRow {
+    let metrics = &cycle.metrics;
+    CycleExportRow {
+        start_time: format_time(cycle.start),
+        end_time: cycle.end.map(format_time),
+        sessions: cycle.session_indices.len(),
+        app_events: cycle.app_events.len(),
+        total_seconds_read: metrics.total_seconds_read,
+        total_pages: metrics.total_pages,
+        total_button_presses: metrics.total_button_presses,
+        dictionary_lookups: metrics.dictionary_lookups,
+        brightness_events: metrics.brightness_events,
+        app_starts: metrics.app_starts,
+        annotations: metrics.annotations,
+        start_battery: metrics.start_battery,
+        end_battery: metrics.end_battery,
+        charger: metrics.charger.clone(),
+        battery_drain: metrics.battery_drain,
+        drain_per_reading_hour: metrics.drain_per_reading_hour,
+        average_brightness: metrics.average_brightness,
+        projected_reading_hours: metrics.projected_reading_hours,
+    }
+}
+
+impl Export for [ChargeCycle] {
+    fn to_csv(&self) -> Result<String, ExportError> {
+        let mut wtr = csv::Writer::from_writer(vec![]);
+        for cycle in self {
+            wtr.serialize(to_export_row(cycle))?;
+        }
+        Ok(String::from_utf8(wtr.into_inner()?)?)
+    }
+
+    fn to_md(&self) -> Result<String, ExportError> {
+        let mut buffer = Vec::new();
+        writeln!(
+            buffer,
+            "| Start | End | Sessions | Minutes Read | Pages | Battery | Drain/h | Avg Brightness | Charger |"
+        )?;
+        writeln!(
+            buffer,
+            "|-------|-----|----------|--------------|-------|---------|---------|----------------|---------|"
+        )?;
+
+        for cycle in self {
+            let row = to_export_row(cycle);
+            let battery = match (row.start_battery, row.end_battery) {
+                (Some(start), Some(end)) => format!("{}% → {}%", start, end),
+                (Some(start), None) => format!("{}% → N/A", start),
+                _ => "N/A".to_string(),
+            };
+            writeln!(
+                buffer,
+                "| {} | {} | {} | {} | {} | {} | {} | {} | {} |",
+                row.start_time,
+                or_na(row.end_time),
+                row.sessions,
+                row.total_seconds_read / 60,
+                row.total_pages,
+                battery,
+                or_na(
+                    row.drain_per_reading_hour
+                        .map(|drain| format!("{:.1}", drain))
+                ),
+                or_na(row.average_brightness.map(|avg| format!("{:.1}", avg))),
+                or_na(row.charger),
+            )?;
+        }
+
+        Ok(String::from_utf8(buffer)?)
+    }
+
+    fn to_json(&self) -> Result<String, ExportError> {
+        serde_json::to_string(self).map_err(ExportError::JsonToString)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ChargeCycleMetrics;
+    use chrono::{DateTime, Utc};
+    use std::str::FromStr;
+
+    fn build_cycles() -> Vec<ChargeCycle> {
+        vec![ChargeCycle {
+            start: DateTime::<Utc>::from_str("2023-01-01T08:00:00Z").unwrap(),
+            end: Some(DateTime::<Utc>::from_str("2023-01-03T08:00:00Z").unwrap()),
+            session_indices: vec![0, 1],
+            app_events: Vec::new(),
+            metrics: ChargeCycleMetrics {
+                total_seconds_read: 7200,
+                total_pages: 80,
+                start_battery: Some(90),
+                end_battery: Some(70),
+                battery_drain: Some(20),
+                drain_per_reading_hour: Some(10.0),
+                average_brightness: Some(42.5),
+                charger: Some("USB".to_string()),
+                ..Default::default()
+            },
+        }]
+    }
+
+    #[test]
+    fn test_cycles_to_csv() {
+        let csv = build_cycles().to_csv().unwrap();
+        let mut lines = csv.lines();
+        assert!(lines
+            .next()
+            .unwrap()
+            .starts_with("start_time,end_time,sessions,"));
+        assert_eq!(
+            lines.next().unwrap(),
+            "2023-01-01T08:00:00Z,2023-01-03T08:00:00Z,2,0,7200,80,0,0,0,0,0,90,70,USB,20,10.0,42.5,"
+        );
+    }
+
+    #[test]
+    fn test_cycles_to_md_and_json() {
+        let cycles = build_cycles();
+        let md = cycles.to_md().unwrap();
+        assert!(md.contains("| 2 | 120 | 80 | 90% → 70% | 10.0 | 42.5 | USB |"));
+        let json = cycles.to_json().unwrap();
+        assert!(json.contains("\"session_indices\":[0,1]"));
+        assert!(json.contains("\"battery_drain\":20"));
+    }
+}
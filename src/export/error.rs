@@ -12,6 +12,14 @@ pub enum ExportError {
     CsvIntoInner(Box<csv::IntoInnerError<csv::Writer<Vec<u8>>>>),
     #[error("serde_json::to_string error: {0}")]
     JsonToString(#[from] serde_json::Error),
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[cfg(feature = "parquet")]
+    #[error("Arrow error: {0}")]
+    Arrow(#[from] arrow_schema::ArrowError),
+    #[cfg(feature = "parquet")]
+    #[error("Parquet error: {0}")]
+    Parquet(#[from] parquet::errors::ParquetError),
 }
 
 impl From<csv::IntoInnerError<csv::Writer<Vec<u8>>>> for ExportError {
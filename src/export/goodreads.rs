@@ -0,0 +1,247 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::export::ExportError;
+use crate::model::{Book, ReadingSession, ReadingSessions};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExclusiveShelf {
+    Read,
+    CurrentlyReading,
+    ToRead,
+}
+
+impl ExclusiveShelf {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExclusiveShelf::Read => "read",
+            ExclusiveShelf::CurrentlyReading => "currently-reading",
+            ExclusiveShelf::ToRead => "to-read",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReadingHistory {
+    pub date_started: Option<DateTime<Utc>>,
+    pub date_read: Option<DateTime<Utc>>,
+    pub shelf: ExclusiveShelf,
+}
+
+#[derive(Serialize)]
+struct GoodreadsRow<'a> {
+    #[serde(rename = "Title")]
+    title: &'a str,
+    #[serde(rename = "Author")]
+    author: &'a str,
+    #[serde(rename = "ISBN")]
+    isbn: &'a str,
+    #[serde(rename = "My Rating")]
+    my_rating: u8,
+    #[serde(rename = "Date Started")]
+    date_started: String,
+    #[serde(rename = "Date Read")]
+    date_read: String,
+    #[serde(rename = "Date Added")]
+    date_added: String,
+    #[serde(rename = "Bookshelves")]
+    bookshelves: String,
+    #[serde(rename = "Exclusive Shelf")]
+    exclusive_shelf: &'static str,
+}
+
+fn goodreads_date(ts: Option<DateTime<Utc>>) -> String {
+    ts.map(|ts| ts.format("%Y/%m/%d").to_string())
+        .unwrap_or_default()
+}
+
+fn goodreads_shelf(name: &str) -> String {
+    name.trim()
+        .to_lowercase()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+pub fn reading_history(book: &Book, sessions: &ReadingSessions) -> ReadingHistory {
+    let mut sessions: Vec<&ReadingSession> = sessions
+        .get_sessions()
+        .iter()
+        .filter(|session| book.matches_session(session))
+        .collect();
+    sessions.sort_by_key(|session| session.time_start);
+
+    let date_started = sessions.first().map(|session| session.time_start);
+    let finished_at = sessions
+        .iter()
+        .find(|session| session.end_progress == Some(100))
+        .map(|session| session.time_end.unwrap_or(session.time_start));
+    let date_read = finished_at.or_else(|| {
+        book.is_finished()
+            .then(|| {
+                sessions
+                    .last()
+                    .map(|session| session.time_end.unwrap_or(session.time_start))
+            })
+            .flatten()
+    });
+
+    let shelf = if date_read.is_some() || book.is_finished() {
+        ExclusiveShelf::Read
+    } else if date_started.is_some() || book.read_status == Some(1) {
+        ExclusiveShelf::CurrentlyReading
+    } else {
+        ExclusiveShelf::ToRead
+    };
+
+    ReadingHistory {
+        date_started,
+        date_read,
+        shelf,
+    }
+}
+
+pub fn to_goodreads_csv(books: &[Book], sessions: &ReadingSessions) -> Result<String, ExportError> {
+    let mut wtr = csv::Writer::from_writer(vec![]);
+    for book in books {
+        let history = reading_history(book, sessions);
+        let bookshelves: Vec<String> = book
+            .shelves
+            .iter()
+            .map(|shelf| goodreads_shelf(shelf))
+            .filter(|shelf| !shelf.is_empty())
+            .collect();
+        wtr.serialize(GoodreadsRow {
+            title: &book.title,
+            author: &book.authors,
+            isbn: book.isbn().unwrap_or_default(),
+            my_rating: 0,
+            date_started: goodreads_date(history.date_started),
+            date_read: goodreads_date(history.date_read),
+            date_added: goodreads_date(history.date_started),
+            bookshelves: bookshelves.join(", "),
+            exclusive_shelf: history.shelf.as_str(),
+        })?;
+    }
+    Ok(String::from_utf8(wtr.into_inner()?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn book(id: &str, title: &str, read_status: Option<u8>) -> Book {
+        let mut book = Book::new(
+            "Frank Herbert".to_string(),
+            title.to_string(),
+            None,
+            id.to_string(),
+        );
+        book.read_status = read_status;
+        book
+    }
+
+    fn add(sessions: &mut ReadingSessions, volume: &str, start: &str, end: &str, progress: u8) {
+        let mut session = ReadingSession::new(
+            DateTime::<Utc>::from_str(start).unwrap(),
+            0,
+            None,
+            Some(volume.to_string()),
+            format!("open-{}", start),
+        );
+        session
+            .complete_session(
+                DateTime::<Utc>::from_str(end).unwrap(),
+                progress,
+                0,
+                600,
+                10,
+                format!("leave-{}", end),
+            )
+            .unwrap();
+        sessions.add_session(session);
+    }
+
+    #[test]
+    fn test_reading_history() {
+        let mut sessions = ReadingSessions::new();
+        add(
+            &mut sessions,
+            "vol1",
+            "2023-01-05T10:00:00Z",
+            "2023-01-05T11:00:00Z",
+            100,
+        );
+        add(
+            &mut sessions,
+            "vol1",
+            "2023-01-01T10:00:00Z",
+            "2023-01-01T11:00:00Z",
+            40,
+        );
+        add(
+            &mut sessions,
+            "vol2",
+            "2023-02-01T10:00:00Z",
+            "2023-02-01T11:00:00Z",
+            10,
+        );
+        add(
+            &mut sessions,
+            "vol3",
+            "2023-03-01T10:00:00Z",
+            "2023-03-01T11:00:00Z",
+            80,
+        );
+
+        let read = reading_history(&book("vol1", "Dune", None), &sessions);
+        assert_eq!(read.shelf, ExclusiveShelf::Read);
+        assert_eq!(
+            read.date_started,
+            Some(DateTime::<Utc>::from_str("2023-01-01T10:00:00Z").unwrap())
+        );
+        assert_eq!(
+            read.date_read,
+            Some(DateTime::<Utc>::from_str("2023-01-05T11:00:00Z").unwrap())
+        );
+
+        let reading = reading_history(&book("vol2", "Messiah", Some(1)), &sessions);
+        assert_eq!(reading.shelf, ExclusiveShelf::CurrentlyReading);
+        assert_eq!(reading.date_read, None);
+
+        let finished = reading_history(&book("vol3", "Children", Some(2)), &sessions);
+        assert_eq!(finished.shelf, ExclusiveShelf::Read);
+        assert_eq!(
+            finished.date_read,
+            Some(DateTime::<Utc>::from_str("2023-03-01T11:00:00Z").unwrap())
+        );
+
+        let unread = reading_history(&book("vol4", "Emperor", Some(0)), &sessions);
+        assert_eq!(unread.shelf, ExclusiveShelf::ToRead);
+    }
+
+    #[test]
+    fn test_goodreads_csv() {
+        let mut sessions = ReadingSessions::new();
+        add(
+            &mut sessions,
+            "vol1",
+            "2023-01-01T10:00:00Z",
+            "2023-01-01T11:00:00Z",
+            100,
+        );
+        let mut dune = book("vol1", "Dune", None);
+        dune.isbn = Some("9780441013593".to_string());
+        dune.shelves = vec!["Sci Fi".to_string(), "Favourites".to_string()];
+        let books = [dune, book("vol2", "Messiah", None)];
+        let expected = [
+            "Title,Author,ISBN,My Rating,Date Started,Date Read,Date Added,Bookshelves,Exclusive Shelf",
+            "Dune,Frank Herbert,9780441013593,0,2023/01/01,2023/01/01,2023/01/01,\"sci-fi, favourites\",read",
+            "Messiah,Frank Herbert,,0,,,,,to-read",
+            "",
+        ]
+        .join("\n");
+        assert_eq!(to_goodreads_csv(&books, &sessions).unwrap(), expected);
+    }
+}
@@ -0,0 +1,168 @@
+use serde::Serialize;
+use std::io::Write;
+
+use crate::export::{Export, ExportError};
+use crate::statistics::heatmap::{ReadingHeatmap, WEEKDAYS};
+
+const CELL_SIZE: usize = 24;
+const LEFT_MARGIN: usize = 40;
+const TOP_MARGIN: usize = 24;
+
+#[derive(Serialize)]
+struct HeatmapExportRow {
+    weekday: String,
+    hour: usize,
+    reading_seconds: f64,
+    brightness_adjustments: u32,
+}
+
+fn to_export_rows(heatmap: &ReadingHeatmap) -> Vec<HeatmapExportRow> {
+    let mut rows = Vec::with_capacity(7 * 24);
+    for (day, weekday) in WEEKDAYS.iter().enumerate() {
+        for hour in 0..24 {
+            rows.push(HeatmapExportRow {
+                weekday: weekday.to_string(),
+                hour,
+                reading_seconds: heatmap.reading_seconds[day][hour],
+                brightness_adjustments: heatmap.brightness_adjustments[day][hour],
+            });
+        }
+    }
+    rows
+}
+
+impl Export for ReadingHeatmap {
+    fn to_csv(&self) -> Result<String, ExportError> {
+        let mut wtr = csv::Writer::from_writer(vec![]);
+        for row in to_export_rows(self) {
+            wtr.serialize(row)?;
+        }
+        Ok(String::from_utf8(wtr.into_inner()?)?)
+    }
+
+    fn to_md(&self) -> Result<String, ExportError> {
+        let mut buffer = Vec::new();
+
+        write!(buffer, "| Day |")?;
+        for hour in 0..24 {
+            write!(buffer, " {:02} |", hour)?;
+        }
+        writeln!(buffer)?;
+        write!(buffer, "|-----|")?;
+        for _ in 0..24 {
+            write!(buffer, "----|")?;
+        }
+        writeln!(buffer)?;
+
+        for (day, weekday) in WEEKDAYS.iter().enumerate() {
+            write!(buffer, "| {} |", weekday)?;
+            for hour in 0..24 {
+                let minutes = heatmap_minutes(self, day, hour);
+                write!(buffer, " {} |", minutes)?;
+            }
+            writeln!(buffer)?;
+        }
+        Ok(String::from_utf8(buffer)?)
+    }
+
+    fn to_json(&self) -> Result<String, ExportError> {
+        serde_json::to_string(self).map_err(ExportError::JsonToString)
+    }
+}
+
+fn heatmap_minutes(heatmap: &ReadingHeatmap, day: usize, hour: usize) -> u64 {
+    (heatmap.reading_seconds[day][hour] / 60.0).round() as u64
+}
+
+impl ReadingHeatmap {
+    pub fn to_svg(&self) -> Result<String, ExportError> {
+        let mut buffer = Vec::new();
+        let width = LEFT_MARGIN + 24 * CELL_SIZE;
+        let height = TOP_MARGIN + 7 * CELL_SIZE;
+        let max = self.max_seconds();
+
+        writeln!(
+            buffer,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\" font-family=\"sans-serif\" font-size=\"10\">",
+            width, height, width, height
+        )?;
+        for hour in 0..24 {
+            writeln!(
+                buffer,
+                "<text x=\"{}\" y=\"{}\" text-anchor=\"middle\">{:02}</text>",
+                LEFT_MARGIN + hour * CELL_SIZE + CELL_SIZE / 2,
+                TOP_MARGIN - 8,
+                hour
+            )?;
+        }
+        for (day, weekday) in WEEKDAYS.iter().enumerate() {
+            let y = TOP_MARGIN + day * CELL_SIZE;
+            writeln!(
+                buffer,
+                "<text x=\"4\" y=\"{}\">{}</text>",
+                y + CELL_SIZE / 2 + 4,
+                weekday
+            )?;
+            for hour in 0..24 {
+                let seconds = self.reading_seconds[day][hour];
+                let opacity = if max > 0.0 { seconds / max } else { 0.0 };
+                writeln!(
+                    buffer,
+                    "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"#1f6feb\" fill-opacity=\"{:.3}\" stroke=\"#d0d7de\"><title>{} {:02}:00 - {} min, {} brightness changes</title></rect>",
+                    LEFT_MARGIN + hour * CELL_SIZE,
+                    y,
+                    CELL_SIZE,
+                    CELL_SIZE,
+                    opacity,
+                    weekday,
+                    hour,
+                    heatmap_minutes(self, day, hour),
+                    self.brightness_adjustments[day][hour]
+                )?;
+                if self.brightness_adjustments[day][hour] > 0 {
+                    writeln!(
+                        buffer,
+                        "<circle cx=\"{}\" cy=\"{}\" r=\"3\" fill=\"#d29922\"/>",
+                        LEFT_MARGIN + hour * CELL_SIZE + CELL_SIZE / 2,
+                        y + CELL_SIZE / 2
+                    )?;
+                }
+            }
+        }
+        writeln!(buffer, "</svg>")?;
+        Ok(String::from_utf8(buffer)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_heatmap() -> ReadingHeatmap {
+        let mut heatmap = ReadingHeatmap::new();
+        heatmap.reading_seconds[0][21] = 1800.0;
+        heatmap.brightness_adjustments[0][21] = 2;
+        heatmap
+    }
+
+    #[test]
+    fn test_heatmap_to_csv() {
+        let csv = build_heatmap().to_csv().unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 1 + 7 * 24);
+        assert_eq!(
+            lines[0],
+            "weekday,hour,reading_seconds,brightness_adjustments"
+        );
+        assert_eq!(lines[1 + 21], "Mon,21,1800.0,2");
+    }
+
+    #[test]
+    fn test_heatmap_to_svg() {
+        let svg = build_heatmap().to_svg().unwrap();
+        assert!(svg.starts_with("<svg"));
+        assert_eq!(svg.matches("<rect").count(), 7 * 24);
+        assert_eq!(svg.matches("<circle").count(), 1);
+        assert!(svg.contains("Mon 21:00 - 30 min, 2 brightness changes"));
+    }
+}
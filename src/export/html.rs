@@ -0,0 +1,759 @@
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+
+use crate::export::{or_na, ExportError};
+use crate::model::{Bookmark, BrightnessEvent, CorrelatedAnalysis, ReadingSession};
+use crate::statistics::heatmap::ReadingHeatmap;
+use crate::statistics::timeseries::{local_date, Granularity, TimeSeries};
+
+const CHART_WIDTH: usize = 720;
+const CHART_HEIGHT: usize = 200;
+const CHART_MARGIN: usize = 32;
+
+const STYLE: &str = "
+body { font-family: -apple-system, 'Segoe UI', Helvetica, Arial, sans-serif; margin: 0 auto; max-width: 960px; padding: 24px; color: #24292f; background: #ffffff; }
+h1 { margin-bottom: 4px; }
+h2 { border-bottom: 1px solid #d0d7de; padding-bottom: 4px; margin-top: 40px; }
+.subtitle { color: #57606a; margin-top: 0; }
+.summary { display: flex; flex-wrap: wrap; gap: 12px; }
+.stat { border: 1px solid #d0d7de; border-radius: 6px; padding: 12px 16px; min-width: 120px; }
+.stat strong { display: block; font-size: 1.6em; }
+.cards { display: grid; grid-template-columns: repeat(auto-fill, minmax(260px, 1fr)); gap: 12px; }
+.card { border: 1px solid #d0d7de; border-radius: 6px; padding: 12px 16px; }
+.card h3 { margin: 0 0 8px 0; font-size: 1.05em; }
+.progress { background: #eaeef2; border-radius: 4px; height: 8px; overflow: hidden; margin: 8px 0; }
+.progress div { background: #1f6feb; height: 100%; }
+.muted { color: #57606a; font-size: 0.9em; }
+blockquote { border-left: 4px solid #d29922; margin: 8px 0; padding: 4px 12px; background: #fff8c5; }
+table { border-collapse: collapse; width: 100%; }
+th, td { border: 1px solid #d0d7de; padding: 4px 8px; text-align: left; }
+th { background: #f6f8fa; }
+.vocabulary { columns: 3 180px; list-style: none; padding: 0; }
+svg { max-width: 100%; height: auto; }
+";
+
+struct BookCard {
+    title: String,
+    progress: u8,
+    seconds_read: u64,
+    sessions: usize,
+    last_read: DateTime<Utc>,
+}
+
+pub struct HtmlReport<'a, Tz: TimeZone> {
+    analysis: &'a CorrelatedAnalysis,
+    bookmarks: Option<&'a [Bookmark]>,
+    tz: Tz,
+    title: String,
+    granularity: Granularity,
+}
+
+impl<'a> HtmlReport<'a, Utc> {
+    pub fn new(analysis: &'a CorrelatedAnalysis) -> Self {
+        Self {
+            analysis,
+            bookmarks: None,
+            tz: Utc,
+            title: "Reading Report".to_string(),
+            granularity: Granularity::Monthly,
+        }
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn format_minutes(seconds: u64) -> String {
+    let minutes = seconds / 60;
+    if minutes >= 60 {
+        format!("{}h {:02}m", minutes / 60, minutes % 60)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+impl<'a, Tz: TimeZone> HtmlReport<'a, Tz> {
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    pub fn with_granularity(mut self, granularity: Granularity) -> Self {
+        self.granularity = granularity;
+        self
+    }
+
+    pub fn with_bookmarks(mut self, bookmarks: &'a [Bookmark]) -> Self {
+        self.bookmarks = Some(bookmarks);
+        self
+    }
+
+    pub fn in_timezone<T: TimeZone>(self, tz: T) -> HtmlReport<'a, T> {
+        HtmlReport {
+            analysis: self.analysis,
+            bookmarks: self.bookmarks,
+            tz,
+            title: self.title,
+            granularity: self.granularity,
+        }
+    }
+
+    fn format_local(&self, ts: DateTime<Utc>) -> String {
+        ts.with_timezone(&self.tz)
+            .naive_local()
+            .format("%Y-%m-%d %H:%M")
+            .to_string()
+    }
+
+    fn local_date(&self, ts: DateTime<Utc>) -> NaiveDate {
+        local_date(ts, &self.tz)
+    }
+
+    pub fn render(&self) -> Result<String, ExportError> {
+        let mut buffer = Vec::new();
+        writeln!(buffer, "<!DOCTYPE html>")?;
+        writeln!(buffer, "<html lang=\"en\">")?;
+        writeln!(buffer, "<head>")?;
+        writeln!(buffer, "<meta charset=\"utf-8\">")?;
+        writeln!(
+            buffer,
+            "<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">"
+        )?;
+        writeln!(buffer, "<title>{}</title>", escape(&self.title))?;
+        writeln!(buffer, "<style>{}</style>", STYLE)?;
+        writeln!(buffer, "</head>")?;
+        writeln!(buffer, "<body>")?;
+        writeln!(buffer, "<h1>{}</h1>", escape(&self.title))?;
+
+        self.write_summary(&mut buffer)?;
+        self.write_reading_chart(&mut buffer)?;
+        writeln!(buffer, "<h2>Reading Heatmap</h2>")?;
+        write!(
+            buffer,
+            "{}",
+            ReadingHeatmap::from_correlated(self.analysis, &self.tz).to_svg()?
+        )?;
+        self.write_books(&mut buffer)?;
+        self.write_highlights(&mut buffer)?;
+        self.write_vocabulary(&mut buffer)?;
+        self.write_brightness(&mut buffer)?;
+        self.write_cycles(&mut buffer)?;
+
+        writeln!(buffer, "</body>")?;
+        writeln!(buffer, "</html>")?;
+        Ok(String::from_utf8(buffer)?)
+    }
+
+    fn write_summary(&self, buffer: &mut Vec<u8>) -> Result<(), ExportError> {
+        let sessions: Vec<&ReadingSession> = self
+            .analysis
+            .sessions
+            .iter()
+            .map(|correlated| &correlated.session)
+            .filter(|session| session.is_valid())
+            .collect();
+        let seconds: u64 = sessions
+            .iter()
+            .filter_map(|session| session.seconds_read)
+            .sum();
+        let pages: u64 = sessions
+            .iter()
+            .filter_map(|session| session.pages_turned)
+            .sum();
+        let first = sessions.iter().map(|session| session.time_start).min();
+        let last = sessions.iter().map(|session| session.time_start).max();
+        if let (Some(first), Some(last)) = (first, last) {
+            writeln!(
+                buffer,
+                "<p class=\"subtitle\">{} – {}</p>",
+                self.local_date(first),
+                self.local_date(last)
+            )?;
+        }
+
+        writeln!(buffer, "<div class=\"summary\">")?;
+        for (label, value) in [
+            ("Reading time", format_minutes(seconds)),
+            ("Sessions", sessions.len().to_string()),
+            ("Pages turned", pages.to_string()),
+            ("Books", self.book_cards().len().to_string()),
+            ("Highlights", self.highlights().len().to_string()),
+            ("Words looked up", self.vocabulary().len().to_string()),
+        ] {
+            writeln!(
+                buffer,
+                "<div class=\"stat\"><strong>{}</strong>{}</div>",
+                escape(&value),
+                label
+            )?;
+        }
+        writeln!(buffer, "</div>")?;
+        Ok(())
+    }
+
+    fn write_reading_chart(&self, buffer: &mut Vec<u8>) -> Result<(), ExportError> {
+        writeln!(buffer, "<h2>Reading Time</h2>")?;
+        let series = TimeSeries::from_correlated(self.analysis, self.granularity, &self.tz);
+        let buckets = series.labeled();
+        if buckets.is_empty() {
+            writeln!(buffer, "<p class=\"muted\">No reading sessions.</p>")?;
+            return Ok(());
+        }
+
+        let max_minutes = buckets
+            .iter()
+            .map(|(_, metrics)| metrics.seconds_read / 60)
+            .max()
+            .unwrap_or(0)
+            .max(1);
+        let plot_width = CHART_WIDTH - 2 * CHART_MARGIN;
+        let plot_height = CHART_HEIGHT - 2 * CHART_MARGIN;
+        let slot = plot_width as f64 / buckets.len() as f64;
+        let bar_width = (slot * 0.8).max(1.0);
+
+        writeln!(
+            buffer,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\" font-family=\"sans-serif\" font-size=\"10\">",
+            CHART_WIDTH, CHART_HEIGHT, CHART_WIDTH, CHART_HEIGHT
+        )?;
+        writeln!(
+            buffer,
+            "<text x=\"4\" y=\"{}\">{} min</text>",
+            CHART_MARGIN - 8,
+            max_minutes
+        )?;
+        writeln!(
+            buffer,
+            "<line x1=\"{0}\" y1=\"{1}\" x2=\"{2}\" y2=\"{1}\" stroke=\"#d0d7de\"/>",
+            CHART_MARGIN,
+            CHART_HEIGHT - CHART_MARGIN,
+            CHART_WIDTH - CHART_MARGIN
+        )?;
+        let label_every = buckets.len().div_ceil(12);
+        for (index, (label, metrics)) in buckets.iter().enumerate() {
+            let minutes = metrics.seconds_read / 60;
+            let height = minutes as f64 / max_minutes as f64 * plot_height as f64;
+            let x = CHART_MARGIN as f64 + index as f64 * slot + (slot - bar_width) / 2.0;
+            writeln!(
+                buffer,
+                "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"#1f6feb\"><title>{}: {}</title></rect>",
+                x,
+                (CHART_HEIGHT - CHART_MARGIN) as f64 - height,
+                bar_width,
+                height,
+                escape(label),
+                format_minutes(metrics.seconds_read)
+            )?;
+            if index % label_every == 0 {
+                writeln!(
+                    buffer,
+                    "<text x=\"{:.1}\" y=\"{}\" text-anchor=\"middle\">{}</text>",
+                    x + bar_width / 2.0,
+                    CHART_HEIGHT - CHART_MARGIN + 14,
+                    escape(label)
+                )?;
+            }
+        }
+        writeln!(buffer, "</svg>")?;
+        Ok(())
+    }
+
+    fn book_cards(&self) -> Vec<BookCard> {
+        let mut cards: HashMap<String, BookCard> = HashMap::new();
+        for correlated in &self.analysis.sessions {
+            let session = &correlated.session;
+            if !session.is_valid() {
+                continue;
+            }
+            let Some(key) = session
+                .volume_id
+                .clone()
+                .or_else(|| session.book_title.clone())
+            else {
+                continue;
+            };
+            let card = cards.entry(key.clone()).or_insert_with(|| BookCard {
+                title: key,
+                progress: 0,
+                seconds_read: 0,
+                sessions: 0,
+                last_read: session.time_start,
+            });
+            if let Some(title) = &session.book_title {
+                card.title = title.clone();
+            } else if let Some(annotation) = correlated.annotations.first() {
+                if !annotation.book_title.is_empty() {
+                    card.title = annotation.book_title.clone();
+                }
+            }
+            card.progress = card
+                .progress
+                .max(session.end_progress.unwrap_or(session.start_progress));
+            card.seconds_read += session.seconds_read.unwrap_or(0);
+            card.sessions += 1;
+            card.last_read = card.last_read.max(session.time_start);
+        }
+        let mut cards: Vec<BookCard> = cards.into_values().collect();
+        cards.sort_by(|a, b| b.last_read.cmp(&a.last_read).then(a.title.cmp(&b.title)));
+        cards
+    }
+
+    fn write_books(&self, buffer: &mut Vec<u8>) -> Result<(), ExportError> {
+        writeln!(buffer, "<h2>Books</h2>")?;
+        let cards = self.book_cards();
+        if cards.is_empty() {
+            writeln!(buffer, "<p class=\"muted\">No books.</p>")?;
+            return Ok(());
+        }
+        writeln!(buffer, "<div class=\"cards\">")?;
+        for card in cards {
+            writeln!(buffer, "<div class=\"card\">")?;
+            writeln!(buffer, "<h3>{}</h3>", escape(&card.title))?;
+            writeln!(
+                buffer,
+                "<div class=\"progress\"><div style=\"width: {}%\"></div></div>",
+                card.progress.min(100)
+            )?;
+            writeln!(
+                buffer,
+                "<div class=\"muted\">{}% · {} · {} sessions · last read {}</div>",
+                card.progress,
+                format_minutes(card.seconds_read),
+                card.sessions,
+                self.local_date(card.last_read)
+            )?;
+            writeln!(buffer, "</div>")?;
+        }
+        writeln!(buffer, "</div>")?;
+        Ok(())
+    }
+
+    fn highlights(&self) -> Vec<&'a Bookmark> {
+        let analysis = self.analysis;
+        let mut highlights: Vec<&Bookmark> = match self.bookmarks {
+            Some(bookmarks) => bookmarks.iter().collect(),
+            None => analysis
+                .sessions
+                .iter()
+                .flat_map(|session| session.annotations.iter())
+                .chain(analysis.orphans.annotations.iter())
+                .collect(),
+        };
+        highlights.sort_by(|a, b| a.create_date.cmp(&b.create_date));
+        highlights
+    }
+
+    fn write_highlights(&self, buffer: &mut Vec<u8>) -> Result<(), ExportError> {
+        writeln!(buffer, "<h2>Highlights</h2>")?;
+        let mut by_book: BTreeMap<&str, Vec<&Bookmark>> = BTreeMap::new();
+        for highlight in self.highlights() {
+            let title = if highlight.book_title.is_empty() {
+                highlight.book_id.as_str()
+            } else {
+                highlight.book_title.as_str()
+            };
+            by_book.entry(title).or_default().push(highlight);
+        }
+        if by_book.is_empty() {
+            writeln!(buffer, "<p class=\"muted\">No highlights.</p>")?;
+            return Ok(());
+        }
+        for (title, highlights) in by_book {
+            writeln!(buffer, "<h3>{}</h3>", escape(title))?;
+            for highlight in highlights {
+                let created = highlight
+                    .created_at()
+                    .map(|ts| self.format_local(ts))
+                    .unwrap_or_else(|| highlight.create_date.clone());
+                writeln!(
+                    buffer,
+                    "<blockquote>{}<div class=\"muted\">{:.0}% · {}</div></blockquote>",
+                    escape(&highlight.content),
+                    highlight.chapter_progress * 100.0,
+                    escape(&created)
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    fn vocabulary(&self) -> Vec<(String, String, usize)> {
+        let analysis = self.analysis;
+        let mut counts: HashMap<(String, String), usize> = HashMap::new();
+        for word in analysis
+            .sessions
+            .iter()
+            .flat_map(|session| session.dictionary.iter())
+            .chain(analysis.orphans.dictionary.iter())
+        {
+            *counts
+                .entry((word.term().to_lowercase(), word.lang().to_string()))
+                .or_insert(0) += 1;
+        }
+        let mut vocabulary: Vec<(String, String, usize)> = counts
+            .into_iter()
+            .map(|((term, lang), count)| (term, lang, count))
+            .collect();
+        vocabulary.sort_by(|a, b| b.2.cmp(&a.2).then_with(|| a.0.cmp(&b.0)));
+        vocabulary
+    }
+
+    fn write_vocabulary(&self, buffer: &mut Vec<u8>) -> Result<(), ExportError> {
+        writeln!(buffer, "<h2>Vocabulary</h2>")?;
+        let vocabulary = self.vocabulary();
+        if vocabulary.is_empty() {
+            writeln!(buffer, "<p class=\"muted\">No dictionary lookups.</p>")?;
+            return Ok(());
+        }
+        writeln!(buffer, "<ul class=\"vocabulary\">")?;
+        for (term, lang, count) in vocabulary {
+            let times = if count > 1 {
+                format!(" ×{}", count)
+            } else {
+                String::new()
+            };
+            writeln!(
+                buffer,
+                "<li>{} <span class=\"muted\">({}){}</span></li>",
+                escape(&term),
+                escape(&lang),
+                times
+            )?;
+        }
+        writeln!(buffer, "</ul>")?;
+        Ok(())
+    }
+
+    fn write_brightness(&self, buffer: &mut Vec<u8>) -> Result<(), ExportError> {
+        writeln!(buffer, "<h2>Brightness</h2>")?;
+        let analysis = self.analysis;
+        let mut brightness: Vec<&BrightnessEvent> = analysis
+            .sessions
+            .iter()
+            .flat_map(|session| session.brightness.iter())
+            .chain(analysis.orphans.brightness.iter())
+            .collect();
+        let mut natural_light: Vec<&BrightnessEvent> = analysis
+            .sessions
+            .iter()
+            .flat_map(|session| session.natural_light.iter())
+            .chain(analysis.orphans.natural_light.iter())
+            .collect();
+        brightness.sort_by_key(|event| event.timestamp);
+        natural_light.sort_by_key(|event| event.timestamp);
+
+        let timestamps = brightness
+            .iter()
+            .chain(natural_light.iter())
+            .map(|event| event.timestamp);
+        let (Some(first), Some(last)) = (timestamps.clone().min(), timestamps.max()) else {
+            writeln!(buffer, "<p class=\"muted\">No brightness changes.</p>")?;
+            return Ok(());
+        };
+
+        let span = (last - first).num_seconds().max(1) as f64;
+        let plot_width = (CHART_WIDTH - 2 * CHART_MARGIN) as f64;
+        let plot_height = (CHART_HEIGHT - 2 * CHART_MARGIN) as f64;
+        let point = |event: &BrightnessEvent| {
+            let x = CHART_MARGIN as f64
+                + (event.timestamp - first).num_seconds() as f64 / span * plot_width;
+            let y = CHART_MARGIN as f64
+                + (1.0 - event.brightness.percentage.min(100) as f64 / 100.0) * plot_height;
+            format!("{:.1},{:.1}", x, y)
+        };
+
+        writeln!(
+            buffer,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\" font-family=\"sans-serif\" font-size=\"10\">",
+            CHART_WIDTH, CHART_HEIGHT, CHART_WIDTH, CHART_HEIGHT
+        )?;
+        writeln!(
+            buffer,
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"none\" stroke=\"#d0d7de\"/>",
+            CHART_MARGIN,
+            CHART_MARGIN,
+            CHART_WIDTH - 2 * CHART_MARGIN,
+            CHART_HEIGHT - 2 * CHART_MARGIN
+        )?;
+        writeln!(
+            buffer,
+            "<text x=\"4\" y=\"{}\">100%</text><text x=\"4\" y=\"{}\">0%</text>",
+            CHART_MARGIN + 4,
+            CHART_HEIGHT - CHART_MARGIN
+        )?;
+        writeln!(
+            buffer,
+            "<text x=\"{}\" y=\"{}\">{}</text><text x=\"{}\" y=\"{}\" text-anchor=\"end\">{}</text>",
+            CHART_MARGIN,
+            CHART_HEIGHT - CHART_MARGIN + 14,
+            self.local_date(first),
+            CHART_WIDTH - CHART_MARGIN,
+            CHART_HEIGHT - CHART_MARGIN + 14,
+            self.local_date(last)
+        )?;
+        for (events, color, label) in [
+            (&brightness, "#1f6feb", "Brightness"),
+            (&natural_light, "#d29922", "Natural light"),
+        ] {
+            if events.is_empty() {
+                continue;
+            }
+            let points: Vec<String> = events.iter().map(|event| point(event)).collect();
+            writeln!(
+                buffer,
+                "<polyline points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"1.5\"><title>{}</title></polyline>",
+                points.join(" "),
+                color,
+                label
+            )?;
+        }
+        writeln!(buffer, "</svg>")?;
+        writeln!(
+            buffer,
+            "<p class=\"muted\"><span style=\"color: #1f6feb\">■</span> Brightness ({} changes) <span style=\"color: #d29922\">■</span> Natural light ({} changes)</p>",
+            brightness.len(),
+            natural_light.len()
+        )?;
+        Ok(())
+    }
+
+    fn write_cycles(&self, buffer: &mut Vec<u8>) -> Result<(), ExportError> {
+        writeln!(buffer, "<h2>Charge Cycles</h2>")?;
+        if self.analysis.cycles.is_empty() {
+            writeln!(buffer, "<p class=\"muted\">No charge cycles.</p>")?;
+            return Ok(());
+        }
+        writeln!(buffer, "<table>")?;
+        writeln!(
+            buffer,
+            "<tr><th>Start</th><th>End</th><th>Sessions</th><th>Reading time</th><th>Battery</th><th>Drain/h</th><th>Avg brightness</th></tr>"
+        )?;
+        for cycle in &self.analysis.cycles {
+            let metrics = &cycle.metrics;
+            let battery = match (metrics.start_battery, metrics.end_battery) {
+                (Some(start), Some(end)) => format!("{}% → {}%", start, end),
+                (Some(start), None) => format!("{}% → N/A", start),
+                _ => "N/A".to_string(),
+            };
+            writeln!(
+                buffer,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                self.format_local(cycle.start),
+                or_na(cycle.end.map(|end| self.format_local(end))),
+                cycle.session_indices.len(),
+                format_minutes(metrics.total_seconds_read),
+                battery,
+                or_na(metrics.drain_per_reading_hour.map(|drain| format!("{:.1}%", drain))),
+                or_na(metrics.average_brightness.map(|avg| format!("{:.0}%", avg)))
+            )?;
+        }
+        writeln!(buffer, "</table>")?;
+        Ok(())
+    }
+}
+
+impl CorrelatedAnalysis {
+    pub fn to_html(&self) -> Result<String, ExportError> {
+        HtmlReport::new(self).render()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        Brightness, ChargeCycle, ChargeCycleMetrics, CorrelatedSession, DictionaryWord,
+        OrphanEvents,
+    };
+    use chrono::FixedOffset;
+    use std::str::FromStr;
+
+    fn ts(value: &str) -> DateTime<Utc> {
+        DateTime::<Utc>::from_str(value).unwrap()
+    }
+
+    fn build_analysis() -> CorrelatedAnalysis {
+        let mut session = ReadingSession::new(
+            ts("2023-01-01T22:30:00Z"),
+            10,
+            Some("Dune & Sons".to_string()),
+            Some("book1".to_string()),
+            "open1".to_string(),
+        );
+        session
+            .complete_session(ts("2023-01-01T23:30:00Z"), 35, 3, 3600, 30, "leave1".into())
+            .unwrap();
+        let mut correlated = CorrelatedSession::new(session);
+        correlated.dictionary.push(DictionaryWord::new(
+            "Sietch".to_string(),
+            "en".to_string(),
+            Some(correlated.session.id),
+        ));
+        correlated.brightness.push(BrightnessEvent::new(
+            Brightness::new("manual".to_string(), 40),
+            ts("2023-01-01T22:40:00Z"),
+        ));
+        correlated.brightness.push(BrightnessEvent::new(
+            Brightness::new("manual".to_string(), 20),
+            ts("2023-01-01T23:10:00Z"),
+        ));
+        correlated.annotations.push(Bookmark {
+            content_id: "bm1".to_string(),
+            content: "Fear is the <mind-killer>.".to_string(),
+            book_id: "book1".to_string(),
+            book_title: "Dune & Sons".to_string(),
+            color: 0,
+            chapter_progress: 0.25,
+            create_date: "2023-01-01T23:00:00Z".to_string(),
+            write_date: "2023-01-01T23:00:00Z".to_string(),
+            annotation: None,
+            chapter_index: None,
+        });
+        let mut orphans = OrphanEvents::default();
+        orphans.dictionary.push(DictionaryWord::new(
+            "sietch".to_string(),
+            "en".to_string(),
+            None,
+        ));
+        let mut short = ReadingSession::new(
+            ts("2023-01-02T09:00:00Z"),
+            0,
+            Some("Glimpse".to_string()),
+            Some("book2".to_string()),
+            "open2".to_string(),
+        );
+        short
+            .complete_session(ts("2023-01-02T09:00:30Z"), 1, 0, 30, 1, "leave2".into())
+            .unwrap();
+        let partial = ReadingSession::new(
+            ts("2023-01-02T10:00:00Z"),
+            35,
+            Some("Dune & Sons".to_string()),
+            Some("book1".to_string()),
+            "open3".to_string(),
+        );
+        CorrelatedAnalysis {
+            sessions: vec![
+                correlated,
+                CorrelatedSession::new(short),
+                CorrelatedSession::new(partial),
+            ],
+            orphans,
+            cycles: vec![ChargeCycle {
+                start: ts("2023-01-01T08:00:00Z"),
+                end: None,
+                session_indices: vec![0],
+                app_events: Vec::new(),
+                metrics: ChargeCycleMetrics {
+                    total_seconds_read: 3600,
+                    start_battery: Some(90),
+                    ..Default::default()
+                },
+            }],
+            app_start_counts_by_day: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_html_report_sections() {
+        let analysis = build_analysis();
+        let html = HtmlReport::new(&analysis)
+            .with_title("2023 <Reading>")
+            .render()
+            .unwrap();
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<title>2023 &lt;Reading&gt;</title>"));
+        for section in [
+            "Reading Time",
+            "Reading Heatmap",
+            "Books",
+            "Highlights",
+            "Vocabulary",
+            "Brightness",
+            "Charge Cycles",
+        ] {
+            assert!(
+                html.contains(&format!("<h2>{}</h2>", section)),
+                "{}",
+                section
+            );
+        }
+        assert!(html.contains("<div class=\"stat\"><strong>1h 00m</strong>Reading time</div>"));
+        assert!(html.contains("<div class=\"stat\"><strong>1</strong>Sessions</div>"));
+        assert!(html.contains("<div class=\"stat\"><strong>1</strong>Books</div>"));
+        assert!(html.contains("35% · 1h 00m · 1 sessions"));
+        assert!(!html.contains("Glimpse"));
+        assert!(html.contains("<h3>Dune &amp; Sons</h3>"));
+        assert!(html.contains("<div style=\"width: 35%\"></div>"));
+        assert!(html.contains("Fear is the &lt;mind-killer&gt;."));
+        assert!(html.contains("<li>sietch <span class=\"muted\">(en) ×2</span></li>"));
+        assert!(html.contains("<polyline points=\""));
+        assert!(html.contains("<td>90% → N/A</td>"));
+        assert!(!html.contains("<script"));
+        assert!(!html.contains("<link"));
+    }
+
+    #[test]
+    fn test_html_report_timezone() {
+        let analysis = build_analysis();
+        let html = HtmlReport::new(&analysis)
+            .with_granularity(Granularity::Daily)
+            .in_timezone(FixedOffset::east_opt(2 * 3600).unwrap())
+            .render()
+            .unwrap();
+        assert!(html.contains("<title>2023-01-02: 1h 00m</title>"));
+        assert!(html.contains("<td>2023-01-01 10:00</td>"));
+        assert!(analysis.to_html().unwrap().contains("2023-01: 1h 00m"));
+    }
+
+    #[test]
+    fn test_html_report_from_parsed_database() {
+        let db = rusqlite::Connection::open_in_memory().unwrap();
+        db.execute_batch(
+            "CREATE TABLE AnalyticsEvents (Id TEXT, Type TEXT, Timestamp TEXT, Attributes TEXT, Metrics TEXT);
+             CREATE TABLE content (ContentID TEXT, ContentType INTEGER, Title TEXT, Attribution TEXT, BookID TEXT);
+             CREATE TABLE Bookmark (BookmarkID TEXT, ContentID TEXT, Text TEXT, VolumeID TEXT, Color INTEGER,
+                 ChapterProgress REAL, DateCreated TEXT, DateModified TEXT, Annotation TEXT);
+             INSERT INTO content VALUES ('book1', 6, 'Dune', 'Frank Herbert', NULL);
+             INSERT INTO Bookmark VALUES ('bm1', 'book1!ch1', 'Fear is the mind-killer.', 'book1', 1, 0.2,
+                 '2023-01-01T10:05:00.000', '2023-01-01T10:05:00.000', NULL);
+             INSERT INTO AnalyticsEvents VALUES ('open1', 'OpenContent', '2023-01-01T10:00:00Z',
+                 '{\"progress\":\"0\",\"volumeid\":\"book1\"}', NULL);
+             INSERT INTO AnalyticsEvents VALUES ('leave1', 'LeaveContent', '2023-01-01T10:10:00Z',
+                 '{\"progress\":\"5\",\"volumeid\":\"book1\"}',
+                 '{\"ButtonPressCount\":1,\"SecondsRead\":600,\"PagesTurned\":3}');",
+        )
+        .unwrap();
+
+        let analysis = crate::Parser::parse_correlated(&db).unwrap();
+        let html = analysis.to_html().unwrap();
+        assert!(html.contains("Fear is the mind-killer."));
+        assert!(!html.contains("No highlights."));
+
+        let config = crate::CorrelationConfig {
+            attach_bookmarks: false,
+            ..Default::default()
+        };
+        let analysis = crate::Parser::parse_correlated_with(&db, &config, &Utc).unwrap();
+        assert!(analysis.to_html().unwrap().contains("No highlights."));
+        let bookmarks = crate::get_bookmarks(&db).unwrap();
+        let html = HtmlReport::new(&analysis)
+            .with_bookmarks(&bookmarks)
+            .render()
+            .unwrap();
+        assert!(html.contains("Fear is the mind-killer."));
+    }
+}
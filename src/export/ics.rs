@@ -0,0 +1,214 @@
+use chrono::{DateTime, Utc};
+use std::io::Write;
+
+use crate::export::ExportError;
+use crate::model::{ReadingSession, StitchedSession};
+
+const PRODID: &str = "-//kobo-db-tools//Reading Sessions//EN";
+const MAX_LINE_OCTETS: usize = 75;
+
+fn ics_time(ts: DateTime<Utc>) -> String {
+    ts.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn push_line(buffer: &mut String, line: &str) {
+    let mut octets = 0;
+    for c in line.chars() {
+        if octets + c.len_utf8() > MAX_LINE_OCTETS {
+            buffer.push_str("\r\n ");
+            octets = 1;
+        }
+        buffer.push(c);
+        octets += c.len_utf8();
+    }
+    buffer.push_str("\r\n");
+}
+
+fn format_duration(seconds: u64) -> String {
+    let minutes = seconds / 60;
+    if minutes >= 60 {
+        format!("{}h {:02}m", minutes / 60, minutes % 60)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+fn describe(session: &ReadingSession, parts: usize) -> Result<String, ExportError> {
+    let mut description = Vec::new();
+    let end = session.time_end.unwrap_or(session.time_start);
+    let elapsed = (end - session.time_start).num_seconds().max(0) as u64;
+    let read = session.seconds_read.unwrap_or(elapsed);
+    writeln!(description, "Duration: {}", format_duration(read))?;
+    match session.end_progress {
+        Some(end_progress) => writeln!(
+            description,
+            "Progress: {}% → {}% ({:+})",
+            session.start_progress,
+            end_progress,
+            end_progress as i16 - session.start_progress as i16
+        )?,
+        None => writeln!(description, "Progress: {}%", session.start_progress)?,
+    }
+    if let Some(pages) = session.pages_turned {
+        writeln!(description, "Pages turned: {}", pages)?;
+    }
+    if parts > 1 {
+        writeln!(description, "Stitched from {} sessions", parts)?;
+    }
+    Ok(String::from_utf8(description)?.trim_end().to_string())
+}
+
+fn push_event(
+    buffer: &mut String,
+    session: &ReadingSession,
+    parts: usize,
+) -> Result<(), ExportError> {
+    let Some(end) = session.time_end else {
+        return Ok(());
+    };
+    let title = session
+        .book_title
+        .as_deref()
+        .or(session.volume_id.as_deref())
+        .unwrap_or("Unknown book");
+    push_line(buffer, "BEGIN:VEVENT");
+    push_line(buffer, &format!("UID:{}@kobo-db-tools", session.id));
+    push_line(buffer, &format!("DTSTAMP:{}", ics_time(end)));
+    push_line(buffer, &format!("DTSTART:{}", ics_time(session.time_start)));
+    push_line(buffer, &format!("DTEND:{}", ics_time(end)));
+    push_line(
+        buffer,
+        &format!("SUMMARY:{}", escape_text(&format!("Reading: {}", title))),
+    );
+    push_line(
+        buffer,
+        &format!("DESCRIPTION:{}", escape_text(&describe(session, parts)?)),
+    );
+    push_line(buffer, "CATEGORIES:Reading");
+    push_line(buffer, "TRANSP:TRANSPARENT");
+    push_line(buffer, "END:VEVENT");
+    Ok(())
+}
+
+fn calendar<'a>(
+    sessions: impl IntoIterator<Item = (&'a ReadingSession, usize)>,
+) -> Result<String, ExportError> {
+    let mut buffer = String::new();
+    push_line(&mut buffer, "BEGIN:VCALENDAR");
+    push_line(&mut buffer, "VERSION:2.0");
+    push_line(&mut buffer, &format!("PRODID:{}", PRODID));
+    push_line(&mut buffer, "CALSCALE:GREGORIAN");
+    push_line(&mut buffer, "X-WR-CALNAME:Reading");
+    for (session, parts) in sessions {
+        push_event(&mut buffer, session, parts)?;
+    }
+    push_line(&mut buffer, "END:VCALENDAR");
+    Ok(buffer)
+}
+
+pub fn sessions_to_ics<'a>(
+    sessions: impl IntoIterator<Item = &'a ReadingSession>,
+) -> Result<String, ExportError> {
+    calendar(sessions.into_iter().map(|session| (session, 1)))
+}
+
+pub fn stitched_to_ics(sessions: &[StitchedSession]) -> Result<String, ExportError> {
+    calendar(
+        sessions
+            .iter()
+            .map(|stitched| (&stitched.session, stitched.parts.len())),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ReadingSessions;
+    use chrono::Duration;
+    use std::str::FromStr;
+
+    fn session(start: &str, end: &str, title: &str) -> ReadingSession {
+        let mut session = ReadingSession::new(
+            DateTime::<Utc>::from_str(start).unwrap(),
+            10,
+            Some(title.to_string()),
+            Some("vol1".to_string()),
+            format!("open-{}", start),
+        );
+        session
+            .complete_session(
+                DateTime::<Utc>::from_str(end).unwrap(),
+                25,
+                0,
+                1500,
+                12,
+                format!("leave-{}", end),
+            )
+            .unwrap();
+        session
+    }
+
+    #[test]
+    fn test_sessions_to_ics() {
+        let session = session(
+            "2023-01-01T21:00:00Z",
+            "2023-01-01T21:30:00Z",
+            "Dune, Part One; Arrakis",
+        );
+        let partial = ReadingSession::new(Utc::now(), 0, None, None, "open-x".to_string());
+        let ics = sessions_to_ics([&session, &partial]).unwrap();
+
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 1);
+        assert!(ics.contains(&format!("UID:{}@kobo-db-tools\r\n", session.id)));
+        assert!(ics.contains("DTSTART:20230101T210000Z\r\nDTEND:20230101T213000Z\r\n"));
+        assert!(ics.contains("SUMMARY:Reading: Dune\\, Part One\\; Arrakis\r\n"));
+        assert!(ics.contains(
+            "DESCRIPTION:Duration: 25m\\nProgress: 10% → 25% (+15)\\nPages turned: 12\r\n"
+        ));
+        assert!(ics.split("\r\n").all(|line| line.len() <= MAX_LINE_OCTETS));
+
+        let again = sessions_to_ics([&session]).unwrap();
+        assert!(again.contains(&format!("UID:{}@kobo-db-tools", session.id)));
+    }
+
+    #[test]
+    fn test_stitched_to_ics_and_folding() {
+        let title = "A Very Long Title ".repeat(6);
+        let mut sessions = ReadingSessions::new();
+        sessions.add_session(session(
+            "2023-01-01T21:00:00Z",
+            "2023-01-01T21:10:00Z",
+            &title,
+        ));
+        sessions.add_session(session(
+            "2023-01-01T21:11:00Z",
+            "2023-01-01T21:20:00Z",
+            &title,
+        ));
+        let stitched = sessions.stitch(Duration::minutes(2));
+        let ics = stitched_to_ics(&stitched).unwrap();
+        assert!(ics.split("\r\n").any(|line| line.starts_with(' ')));
+        let unfolded = ics.replace("\r\n ", "");
+        assert_eq!(unfolded.matches("BEGIN:VEVENT").count(), 1);
+        assert!(unfolded.contains(&format!("SUMMARY:Reading: {}\r\n", title)));
+        assert!(unfolded.contains("\\nStitched from 2 sessions\r\n"));
+        assert!(ics.contains(&format!("UID:{}@kobo-db-tools", stitched[0].parts[0].id)));
+    }
+}
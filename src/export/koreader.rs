@@ -0,0 +1,286 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::Path;
+
+use crate::export::ExportError;
+use crate::model::{Book, ReadingSession, ReadingSessions};
+
+pub const KOREADER_SCHEMA_VERSION: i64 = 20221111;
+
+const KOREADER_SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS book
+    (
+        id integer PRIMARY KEY autoincrement,
+        title text,
+        authors text,
+        notes integer,
+        last_open integer,
+        highlights integer,
+        pages integer,
+        series text,
+        language text,
+        md5 text,
+        total_read_time integer,
+        total_read_pages integer
+    );
+CREATE TABLE IF NOT EXISTS page_stat_data
+    (
+        id_book integer,
+        page integer NOT NULL DEFAULT 0,
+        start_time integer NOT NULL DEFAULT 0,
+        duration integer NOT NULL DEFAULT 0,
+        total_pages integer NOT NULL DEFAULT 0,
+        UNIQUE (id_book, page, start_time),
+        FOREIGN KEY(id_book) REFERENCES book(id)
+    );
+CREATE INDEX IF NOT EXISTS page_stat_data_start_time ON page_stat_data(start_time);
+CREATE TABLE IF NOT EXISTS numbers
+    (
+        idx INTEGER PRIMARY KEY
+    );
+WITH RECURSIVE counter AS
+    (
+        SELECT 1 AS N UNION ALL SELECT N + 1 FROM counter WHERE N < 1000
+    )
+INSERT OR IGNORE INTO numbers SELECT N AS idx FROM counter;
+CREATE VIEW IF NOT EXISTS page_stat AS
+    SELECT id_book, first_page + idx - 1 AS page, start_time, duration / (last_page - first_page + 1) AS duration
+    FROM (
+        SELECT id_book, page, total_pages, pages, start_time, duration,
+            ((page - 1) * pages) / total_pages + 1 AS first_page,
+            max(((page - 1) * pages) / total_pages + 1, (page * pages) / total_pages) AS last_page
+        FROM page_stat_data
+        JOIN book ON book.id = id_book
+    )
+    JOIN numbers ON idx <= (last_page - first_page + 1);
+";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageStat {
+    pub page: u64,
+    pub start_time: i64,
+    pub duration: u64,
+}
+
+pub fn estimate_total_pages(sessions: &[&ReadingSession]) -> u64 {
+    let pages: u64 = sessions.iter().filter_map(|s| s.pages_turned).sum();
+    let progress: u64 = sessions
+        .iter()
+        .filter_map(|s| {
+            s.end_progress
+                .map(|end| end.saturating_sub(s.start_progress) as u64)
+        })
+        .sum();
+    if progress > 0 {
+        (pages * 100).div_ceil(progress).max(1)
+    } else {
+        pages.max(1)
+    }
+}
+
+pub fn spread_session(session: &ReadingSession, total_pages: u64) -> Vec<PageStat> {
+    let seconds = session.seconds_read.unwrap_or(0);
+    let pages = session.pages_turned.unwrap_or(0).max(1);
+    if seconds == 0 {
+        return Vec::new();
+    }
+    let first_page =
+        (session.start_progress.min(100) as u64 * total_pages / 100 + 1).min(total_pages);
+    let duration = seconds / pages;
+    let start = session.time_start.timestamp();
+    (0..pages)
+        .map(|index| {
+            let remainder = if index == pages - 1 {
+                seconds % pages
+            } else {
+                0
+            };
+            PageStat {
+                page: (first_page + index).min(total_pages),
+                start_time: start + (index * duration) as i64,
+                duration: duration + remainder,
+            }
+        })
+        .collect()
+}
+
+fn book_id(db: &Connection, book: &Book, total_pages: u64) -> rusqlite::Result<i64> {
+    let existing: Option<i64> = db
+        .query_row(
+            "SELECT id FROM book WHERE title = ?1 AND authors = ?2",
+            params![book.title, book.authors],
+            |row| row.get(0),
+        )
+        .optional()?;
+    if let Some(id) = existing {
+        return Ok(id);
+    }
+    db.execute(
+        "INSERT INTO book (title, authors, notes, last_open, highlights, pages, series, language, md5, total_read_time, total_read_pages)
+         VALUES (?1, ?2, 0, 0, 0, ?3, ?4, NULL, NULL, 0, 0)",
+        params![book.title, book.authors, total_pages as i64, book.series()],
+    )?;
+    Ok(db.last_insert_rowid())
+}
+
+pub fn write_koreader_statistics(
+    db: &mut Connection,
+    books: &[Book],
+    sessions: &ReadingSessions,
+) -> Result<usize, ExportError> {
+    db.execute_batch(KOREADER_SCHEMA)?;
+    db.pragma_update(None, "user_version", KOREADER_SCHEMA_VERSION)?;
+
+    let tx = db.transaction()?;
+    let mut inserted = 0;
+    for book in books {
+        let book_sessions: Vec<&ReadingSession> = sessions
+            .valid_sessions()
+            .filter(|session| book.matches_session(session))
+            .collect();
+        if book_sessions.is_empty() {
+            continue;
+        }
+        let total_pages = estimate_total_pages(&book_sessions);
+        let id = book_id(&tx, book, total_pages)?;
+        {
+            let mut insert = tx.prepare(
+                "INSERT OR IGNORE INTO page_stat_data (id_book, page, start_time, duration, total_pages)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?;
+            for session in &book_sessions {
+                for stat in spread_session(session, total_pages) {
+                    inserted += insert.execute(params![
+                        id,
+                        stat.page as i64,
+                        stat.start_time,
+                        stat.duration as i64,
+                        total_pages as i64
+                    ])?;
+                }
+            }
+        }
+        tx.execute(
+            "UPDATE book SET
+                last_open = (SELECT COALESCE(MAX(start_time + duration), 0) FROM page_stat_data WHERE id_book = ?1),
+                total_read_time = (SELECT COALESCE(SUM(duration), 0) FROM page_stat_data WHERE id_book = ?1),
+                total_read_pages = (SELECT COUNT(DISTINCT page) FROM page_stat_data WHERE id_book = ?1)
+             WHERE id = ?1",
+            [id],
+        )?;
+    }
+    tx.commit()?;
+    Ok(inserted)
+}
+
+pub fn to_koreader_statistics<P: AsRef<Path>>(
+    path: P,
+    books: &[Book],
+    sessions: &ReadingSessions,
+) -> Result<usize, ExportError> {
+    let mut db = Connection::open(path)?;
+    write_koreader_statistics(&mut db, books, sessions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, Utc};
+    use std::str::FromStr;
+
+    fn session(
+        start: &str,
+        end: &str,
+        progress: (u8, u8),
+        seconds: u64,
+        pages: u64,
+    ) -> ReadingSession {
+        let mut session = ReadingSession::new(
+            DateTime::<Utc>::from_str(start).unwrap(),
+            progress.0,
+            Some("Dune".to_string()),
+            Some("vol1".to_string()),
+            format!("open-{}", start),
+        );
+        session
+            .complete_session(
+                DateTime::<Utc>::from_str(end).unwrap(),
+                progress.1,
+                0,
+                seconds,
+                pages,
+                format!("leave-{}", end),
+            )
+            .unwrap();
+        session
+    }
+
+    #[test]
+    fn test_spread_session() {
+        let session = session(
+            "2023-01-01T10:00:00Z",
+            "2023-01-01T10:10:00Z",
+            (10, 12),
+            605,
+            5,
+        );
+        let stats = spread_session(&session, 250);
+        assert_eq!(stats.len(), 5);
+        assert_eq!(stats[0].page, 26);
+        assert_eq!(stats[4].page, 30);
+        assert_eq!(stats[1].start_time - stats[0].start_time, 121);
+        assert_eq!(stats.iter().map(|stat| stat.duration).sum::<u64>(), 605);
+        assert_eq!(estimate_total_pages(&[&session]), 250);
+    }
+
+    #[test]
+    fn test_write_koreader_statistics() {
+        let mut sessions = ReadingSessions::new();
+        sessions.add_session(session(
+            "2023-01-01T10:00:00Z",
+            "2023-01-01T10:10:00Z",
+            (0, 10),
+            600,
+            10,
+        ));
+        sessions.add_session(session(
+            "2023-01-02T10:00:00Z",
+            "2023-01-02T10:05:00Z",
+            (10, 15),
+            300,
+            5,
+        ));
+        let books = [Book::new(
+            "Frank Herbert".to_string(),
+            "Dune".to_string(),
+            None,
+            "vol1".to_string(),
+        )];
+
+        let mut db = Connection::open_in_memory().unwrap();
+        assert_eq!(
+            write_koreader_statistics(&mut db, &books, &sessions).unwrap(),
+            15
+        );
+        assert_eq!(
+            write_koreader_statistics(&mut db, &books, &sessions).unwrap(),
+            0
+        );
+
+        let (pages, total_time, total_pages): (i64, i64, i64) = db
+            .query_row(
+                "SELECT pages, total_read_time, total_read_pages FROM book WHERE title = 'Dune'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!((pages, total_time, total_pages), (100, 900, 15));
+        let version: i64 = db
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, KOREADER_SCHEMA_VERSION);
+        let view_rows: i64 = db
+            .query_row("SELECT COUNT(*) FROM page_stat", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(view_rows, 15);
+    }
+}
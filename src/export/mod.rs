@@ -1,10 +1,37 @@
+pub mod analysis;
+pub mod anonymize;
+pub mod app_event;
+pub mod book;
 pub mod bookmark;
+pub mod brightness;
+pub mod cycles;
 pub mod dictionary;
 pub mod error;
+pub mod goodreads;
+pub mod heatmap;
+pub mod html;
+pub mod ics;
+pub mod koreader;
+pub mod ndjson;
+#[cfg(feature = "parquet")]
+pub mod parquet;
+pub mod readwise;
 pub mod sessions;
+pub mod vault;
 
+use chrono::{DateTime, SecondsFormat, Utc};
 use error::ExportError;
 
+fn format_time(ts: DateTime<Utc>) -> String {
+    ts.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+fn or_na<T: ToString>(value: Option<T>) -> String {
+    value
+        .map(|value| value.to_string())
+        .unwrap_or_else(|| "N/A".to_string())
+}
+
 pub trait Export {
     fn to_csv(&self) -> Result<String, ExportError>;
     fn to_md(&self) -> Result<String, ExportError>;
@@ -0,0 +1,208 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::io::Write;
+
+use crate::export::ExportError;
+use crate::model::{AppEvent, BrightnessEvent, DictionaryWord, ReadingSession};
+use crate::parser::EventAnalysis;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EventRecord<'a> {
+    Session(&'a ReadingSession),
+    DictionaryLookup {
+        #[serde(flatten)]
+        word: &'a DictionaryWord,
+        count: usize,
+    },
+    Brightness(&'a BrightnessEvent),
+    NaturalLight(&'a BrightnessEvent),
+    AppEvent(&'a AppEvent),
+}
+
+impl EventRecord<'_> {
+    pub fn timestamp(&self) -> Option<DateTime<Utc>> {
+        match self {
+            EventRecord::Session(session) => Some(session.time_start),
+            EventRecord::DictionaryLookup { .. } => None,
+            EventRecord::Brightness(event) | EventRecord::NaturalLight(event) => {
+                Some(event.timestamp)
+            }
+            EventRecord::AppEvent(event) => Some(event.timestamp),
+        }
+    }
+}
+
+pub fn event_records(analysis: &EventAnalysis) -> Vec<EventRecord<'_>> {
+    let mut records: Vec<EventRecord> = Vec::new();
+    if let Some(sessions) = &analysis.sessions {
+        records.extend(sessions.get_sessions().iter().map(EventRecord::Session));
+    }
+    if let Some(history) = &analysis.brightness_history {
+        records.extend(history.events.iter().map(EventRecord::Brightness));
+    }
+    if let Some(history) = &analysis.natural_light_history {
+        records.extend(history.events.iter().map(EventRecord::NaturalLight));
+    }
+    if let Some(events) = &analysis.app_events {
+        records.extend(events.iter().map(EventRecord::AppEvent));
+    }
+    records.sort_by_key(|record| record.timestamp());
+
+    if let Some(terms) = &analysis.terms {
+        let mut lookups: Vec<(&DictionaryWord, &usize)> = terms.iter().collect();
+        lookups.sort_by(|(a, _), (b, _)| {
+            (a.term(), a.lang(), a.session_id()).cmp(&(b.term(), b.lang(), b.session_id()))
+        });
+        records.extend(
+            lookups
+                .into_iter()
+                .map(|(word, &count)| EventRecord::DictionaryLookup { word, count }),
+        );
+    }
+    records
+}
+
+pub struct NdjsonWriter<W: Write> {
+    inner: W,
+    written: usize,
+}
+
+impl<W: Write> NdjsonWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self { inner, written: 0 }
+    }
+
+    pub fn write_record(&mut self, record: &EventRecord) -> Result<(), ExportError> {
+        serde_json::to_writer(&mut self.inner, record)?;
+        self.inner.write_all(b"\n")?;
+        self.written += 1;
+        Ok(())
+    }
+
+    pub fn write_analysis(&mut self, analysis: &EventAnalysis) -> Result<usize, ExportError> {
+        let records = event_records(analysis);
+        for record in &records {
+            self.write_record(record)?;
+        }
+        Ok(records.len())
+    }
+
+    pub fn written(&self) -> usize {
+        self.written
+    }
+
+    pub fn into_inner(mut self) -> Result<W, ExportError> {
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+pub fn to_ndjson(analysis: &EventAnalysis) -> Result<String, ExportError> {
+    let mut writer = NdjsonWriter::new(Vec::new());
+    writer.write_analysis(analysis)?;
+    Ok(String::from_utf8(writer.into_inner()?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{AppEventKind, Brightness, BrightnessHistory, ReadingSessions};
+    use serde_json::Value;
+    use std::collections::HashMap;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_to_ndjson() {
+        let start = DateTime::<Utc>::from_str("2023-01-01T21:00:00Z").unwrap();
+        let mut session = ReadingSession::new(
+            start,
+            10,
+            Some("Dune".to_string()),
+            Some("vol1".to_string()),
+            "open-1".to_string(),
+        );
+        session
+            .complete_session(
+                DateTime::<Utc>::from_str("2023-01-01T21:30:00Z").unwrap(),
+                25,
+                0,
+                1500,
+                12,
+                "leave-1".to_string(),
+            )
+            .unwrap();
+        let session_id = session.id;
+        let mut sessions = ReadingSessions::new();
+        sessions.add_session(session);
+        sessions.add_session(ReadingSession::new(
+            DateTime::<Utc>::from_str("2023-01-01T22:00:00Z").unwrap(),
+            25,
+            Some("Dune".to_string()),
+            Some("vol1".to_string()),
+            "open-2".to_string(),
+        ));
+
+        let mut brightness = BrightnessHistory::new();
+        brightness.insert(BrightnessEvent::new(
+            Brightness::new("manual".to_string(), 40),
+            DateTime::<Utc>::from_str("2023-01-01T21:10:00Z").unwrap(),
+        ));
+        let mut terms = HashMap::new();
+        terms.insert(
+            DictionaryWord::new("spice".to_string(), "en".to_string(), Some(session_id)),
+            2,
+        );
+        let analysis = EventAnalysis {
+            sessions: Some(sessions),
+            terms: Some(terms),
+            brightness_history: Some(brightness),
+            natural_light_history: None,
+            bookmarks: None,
+            books: None,
+            app_events: Some(vec![AppEvent::new(
+                AppEventKind::AppStart,
+                DateTime::<Utc>::from_str("2023-01-01T20:00:00Z").unwrap(),
+                None,
+            )]),
+        };
+
+        let ndjson = to_ndjson(&analysis).unwrap();
+        assert!(ndjson.ends_with('\n'));
+        let lines: Vec<Value> = ndjson
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let types: Vec<&str> = lines
+            .iter()
+            .map(|line| line["type"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            types,
+            [
+                "app_event",
+                "session",
+                "brightness",
+                "session",
+                "dictionary_lookup"
+            ]
+        );
+        assert_eq!(lines[1]["id"], session_id.to_string());
+        assert_eq!(lines[3]["completeness"], "missing_close");
+        assert_eq!(lines[4]["term"], "spice");
+        assert_eq!(lines[4]["count"], 2);
+        assert_eq!(lines[4]["session_id"], session_id.to_string());
+    }
+
+    #[test]
+    fn test_writer_appends() {
+        let event = AppEvent::new(AppEventKind::PluggedIn, Utc::now(), None);
+        let mut writer = NdjsonWriter::new(Vec::new());
+        writer.write_record(&EventRecord::AppEvent(&event)).unwrap();
+        writer.write_record(&EventRecord::AppEvent(&event)).unwrap();
+        assert_eq!(writer.written(), 2);
+        let output = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+        assert_eq!(output.lines().count(), 2);
+        assert!(output.starts_with("{\"type\":\"app_event\",\"kind\":\"PluggedIn\""));
+    }
+}
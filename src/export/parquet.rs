@@ -0,0 +1,359 @@
+use arrow_array::builder::FixedSizeBinaryBuilder;
+use arrow_array::{
+    ArrayRef, FixedSizeBinaryArray, Float64Array, Int16Array, Int64Array, RecordBatch, StringArray,
+    TimestampMillisecondArray, UInt64Array, UInt8Array,
+};
+use arrow_schema::extension::Uuid as UuidExtension;
+use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use chrono::{DateTime, Utc};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::export::ExportError;
+use crate::model::{BrightnessEvent, ChargeCycle, CorrelatedSession, DictionaryWord};
+
+pub trait ParquetExport {
+    fn parquet_schema() -> SchemaRef;
+    fn to_record_batch(&self) -> Result<RecordBatch, ExportError>;
+
+    fn write_parquet<W: Write + Send>(&self, writer: W) -> Result<W, ExportError> {
+        let batch = self.to_record_batch()?;
+        let props = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build();
+        let mut writer = ArrowWriter::try_new(writer, batch.schema(), Some(props))?;
+        writer.write(&batch)?;
+        Ok(writer.into_inner()?)
+    }
+
+    fn to_parquet_file<P: AsRef<Path>>(&self, path: P) -> Result<(), ExportError> {
+        self.write_parquet(File::create(path)?)?;
+        Ok(())
+    }
+}
+
+fn timestamp_field(name: &str, nullable: bool) -> Field {
+    Field::new(
+        name,
+        DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into())),
+        nullable,
+    )
+}
+
+fn uuid_field(name: &str, nullable: bool) -> Field {
+    Field::new(name, DataType::FixedSizeBinary(16), nullable).with_extension_type(UuidExtension)
+}
+
+fn timestamps(values: impl Iterator<Item = Option<DateTime<Utc>>>) -> ArrayRef {
+    Arc::new(
+        values
+            .map(|ts| ts.map(|ts| ts.timestamp_millis()))
+            .collect::<TimestampMillisecondArray>()
+            .with_timezone("UTC"),
+    )
+}
+
+fn uuids(values: impl Iterator<Item = Option<Uuid>>) -> Result<ArrayRef, ExportError> {
+    let mut builder = FixedSizeBinaryBuilder::new(16);
+    for value in values {
+        match value {
+            Some(id) => builder.append_value(id.as_bytes())?,
+            None => builder.append_null(),
+        }
+    }
+    let array: FixedSizeBinaryArray = builder.finish();
+    Ok(Arc::new(array))
+}
+
+fn strings<'a>(values: impl Iterator<Item = Option<&'a str>>) -> ArrayRef {
+    Arc::new(values.collect::<StringArray>())
+}
+
+fn counts(values: impl Iterator<Item = Option<u64>>) -> ArrayRef {
+    Arc::new(values.collect::<UInt64Array>())
+}
+
+fn percentages(values: impl Iterator<Item = Option<u8>>) -> ArrayRef {
+    Arc::new(values.collect::<UInt8Array>())
+}
+
+fn floats(values: impl Iterator<Item = Option<f64>>) -> ArrayRef {
+    Arc::new(values.collect::<Float64Array>())
+}
+
+impl ParquetExport for [CorrelatedSession] {
+    fn parquet_schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            uuid_field("session_id", false),
+            Field::new("completeness", DataType::Utf8, false),
+            Field::new("open_content_id", DataType::Utf8, false),
+            Field::new("leave_content_id", DataType::Utf8, true),
+            timestamp_field("start_time", false),
+            timestamp_field("end_time", true),
+            Field::new("duration_seconds", DataType::Int64, true),
+            Field::new("start_progress", DataType::UInt8, false),
+            Field::new("end_progress", DataType::UInt8, true),
+            Field::new("progress_delta", DataType::Int16, true),
+            Field::new("seconds_read", DataType::UInt64, true),
+            Field::new("pages_turned", DataType::UInt64, true),
+            Field::new("button_press_count", DataType::UInt64, true),
+            Field::new("book_title", DataType::Utf8, true),
+            Field::new("volume_id", DataType::Utf8, true),
+            Field::new("dictionary_lookups", DataType::UInt64, false),
+            Field::new("brightness_events", DataType::UInt64, false),
+            Field::new("natural_light_events", DataType::UInt64, false),
+            Field::new("app_events", DataType::UInt64, false),
+            Field::new("annotations", DataType::UInt64, false),
+        ]))
+    }
+
+    fn to_record_batch(&self) -> Result<RecordBatch, ExportError> {
+        let sessions = || self.iter().map(|correlated| &correlated.session);
+        let columns: Vec<ArrayRef> = vec![
+            uuids(sessions().map(|s| Some(s.id)))?,
+            strings(sessions().map(|s| Some(s.completeness.as_str()))),
+            strings(sessions().map(|s| Some(s.open_content_id.as_str()))),
+            strings(sessions().map(|s| s.leave_content_id.as_deref())),
+            timestamps(sessions().map(|s| Some(s.time_start))),
+            timestamps(sessions().map(|s| s.time_end)),
+            Arc::new(
+                sessions()
+                    .map(|s| s.duration().map(|d| d.num_seconds()))
+                    .collect::<Int64Array>(),
+            ),
+            percentages(sessions().map(|s| Some(s.start_progress))),
+            percentages(sessions().map(|s| s.end_progress)),
+            Arc::new(
+                sessions()
+                    .map(|s| {
+                        s.end_progress
+                            .map(|end| end as i16 - s.start_progress as i16)
+                    })
+                    .collect::<Int16Array>(),
+            ),
+            counts(sessions().map(|s| s.seconds_read)),
+            counts(sessions().map(|s| s.pages_turned)),
+            counts(sessions().map(|s| s.button_press_count)),
+            strings(sessions().map(|s| s.book_title.as_deref())),
+            strings(sessions().map(|s| s.volume_id.as_deref())),
+            counts(self.iter().map(|c| Some(c.dictionary.len() as u64))),
+            counts(self.iter().map(|c| Some(c.brightness.len() as u64))),
+            counts(self.iter().map(|c| Some(c.natural_light.len() as u64))),
+            counts(self.iter().map(|c| Some(c.app_events.len() as u64))),
+            counts(self.iter().map(|c| Some(c.annotations.len() as u64))),
+        ];
+        Ok(RecordBatch::try_new(Self::parquet_schema(), columns)?)
+    }
+}
+
+impl ParquetExport for [DictionaryWord] {
+    fn parquet_schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            Field::new("term", DataType::Utf8, false),
+            Field::new("lang", DataType::Utf8, false),
+            uuid_field("session_id", true),
+        ]))
+    }
+
+    fn to_record_batch(&self) -> Result<RecordBatch, ExportError> {
+        let columns: Vec<ArrayRef> = vec![
+            strings(self.iter().map(|word| Some(word.term()))),
+            strings(self.iter().map(|word| Some(word.lang()))),
+            uuids(self.iter().map(|word| word.session_id()))?,
+        ];
+        Ok(RecordBatch::try_new(Self::parquet_schema(), columns)?)
+    }
+}
+
+impl ParquetExport for [BrightnessEvent] {
+    fn parquet_schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            timestamp_field("timestamp", false),
+            Field::new("method", DataType::Utf8, false),
+            Field::new("adjustment_method", DataType::Utf8, false),
+            Field::new("percentage", DataType::UInt8, false),
+        ]))
+    }
+
+    fn to_record_batch(&self) -> Result<RecordBatch, ExportError> {
+        let columns: Vec<ArrayRef> = vec![
+            timestamps(self.iter().map(|event| Some(event.timestamp))),
+            strings(
+                self.iter()
+                    .map(|event| Some(event.brightness.method.as_str())),
+            ),
+            strings(
+                self.iter()
+                    .map(|event| Some(event.brightness.adjustment_method().as_str())),
+            ),
+            percentages(self.iter().map(|event| Some(event.brightness.percentage))),
+        ];
+        Ok(RecordBatch::try_new(Self::parquet_schema(), columns)?)
+    }
+}
+
+impl ParquetExport for [ChargeCycle] {
+    fn parquet_schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            timestamp_field("start", false),
+            timestamp_field("end", true),
+            Field::new("sessions", DataType::UInt64, false),
+            Field::new("total_seconds_read", DataType::UInt64, false),
+            Field::new("total_pages", DataType::UInt64, false),
+            Field::new("total_button_presses", DataType::UInt64, false),
+            Field::new("dictionary_lookups", DataType::UInt64, false),
+            Field::new("brightness_events", DataType::UInt64, false),
+            Field::new("app_starts", DataType::UInt64, false),
+            Field::new("annotations", DataType::UInt64, false),
+            Field::new("start_battery", DataType::UInt8, true),
+            Field::new("end_battery", DataType::UInt8, true),
+            Field::new("charger", DataType::Utf8, true),
+            Field::new("battery_drain", DataType::UInt8, true),
+            Field::new("drain_per_reading_hour", DataType::Float64, true),
+            Field::new("average_brightness", DataType::Float64, true),
+            Field::new("projected_reading_hours", DataType::Float64, true),
+        ]))
+    }
+
+    fn to_record_batch(&self) -> Result<RecordBatch, ExportError> {
+        let metrics = || self.iter().map(|cycle| &cycle.metrics);
+        let columns: Vec<ArrayRef> = vec![
+            timestamps(self.iter().map(|cycle| Some(cycle.start))),
+            timestamps(self.iter().map(|cycle| cycle.end)),
+            counts(
+                self.iter()
+                    .map(|cycle| Some(cycle.session_indices.len() as u64)),
+            ),
+            counts(metrics().map(|m| Some(m.total_seconds_read))),
+            counts(metrics().map(|m| Some(m.total_pages))),
+            counts(metrics().map(|m| Some(m.total_button_presses))),
+            counts(metrics().map(|m| Some(m.dictionary_lookups as u64))),
+            counts(metrics().map(|m| Some(m.brightness_events as u64))),
+            counts(metrics().map(|m| Some(m.app_starts as u64))),
+            counts(metrics().map(|m| Some(m.annotations as u64))),
+            percentages(metrics().map(|m| m.start_battery)),
+            percentages(metrics().map(|m| m.end_battery)),
+            strings(metrics().map(|m| m.charger.as_deref())),
+            percentages(metrics().map(|m| m.battery_drain)),
+            floats(metrics().map(|m| m.drain_per_reading_hour)),
+            floats(metrics().map(|m| m.average_brightness)),
+            floats(metrics().map(|m| m.projected_reading_hours)),
+        ];
+        Ok(RecordBatch::try_new(Self::parquet_schema(), columns)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Brightness, ReadingSession};
+    use arrow_array::Array;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use parquet::basic::LogicalType;
+    use std::str::FromStr;
+
+    fn correlated() -> Vec<CorrelatedSession> {
+        let mut complete = ReadingSession::new(
+            DateTime::<Utc>::from_str("2023-01-01T21:00:00Z").unwrap(),
+            10,
+            Some("Dune".to_string()),
+            Some("vol1".to_string()),
+            "open-1".to_string(),
+        );
+        complete
+            .complete_session(
+                DateTime::<Utc>::from_str("2023-01-01T21:30:00Z").unwrap(),
+                25,
+                4,
+                1500,
+                12,
+                "leave-1".to_string(),
+            )
+            .unwrap();
+        let partial = ReadingSession::new(
+            DateTime::<Utc>::from_str("2023-01-02T21:00:00Z").unwrap(),
+            25,
+            None,
+            None,
+            "open-2".to_string(),
+        );
+        [complete, partial]
+            .into_iter()
+            .map(CorrelatedSession::new)
+            .collect()
+    }
+
+    #[test]
+    fn test_sessions_parquet_round_trip() {
+        let sessions = correlated();
+        let path = std::env::temp_dir().join(format!("kobo-sessions-{}.parquet", Uuid::new_v4()));
+        sessions.to_parquet_file(&path).unwrap();
+
+        let builder = ParquetRecordBatchReaderBuilder::try_new(File::open(&path).unwrap()).unwrap();
+        let session_id = builder.parquet_schema().column(0);
+        assert_eq!(session_id.logical_type(), Some(LogicalType::Uuid));
+        let batch = builder.build().unwrap().next().unwrap().unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(batch.num_rows(), 2);
+        assert_eq!(
+            batch.schema().field(4).data_type(),
+            &DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into()))
+        );
+        let ids = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<FixedSizeBinaryArray>()
+            .unwrap();
+        assert_eq!(ids.value(0), sessions[0].session.id.as_bytes());
+        let end_progress = batch
+            .column(8)
+            .as_any()
+            .downcast_ref::<UInt8Array>()
+            .unwrap();
+        assert_eq!(end_progress.value(0), 25);
+        assert!(end_progress.is_null(1));
+        let titles = batch
+            .column(13)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!(titles.value(0), "Dune");
+        assert!(titles.is_null(1));
+    }
+
+    #[test]
+    fn test_event_batches() {
+        let words = [
+            DictionaryWord::new("spice".to_string(), "en".to_string(), Some(Uuid::nil())),
+            DictionaryWord::new("melange".to_string(), "en".to_string(), None),
+        ];
+        let batch = words.to_record_batch().unwrap();
+        assert_eq!(batch.num_rows(), 2);
+        assert!(batch.column(2).is_null(1));
+
+        let events = [BrightnessEvent::new(
+            Brightness::new("Manual".to_string(), 40),
+            DateTime::<Utc>::from_str("2023-01-01T21:00:00Z").unwrap(),
+        )];
+        let batch = events.to_record_batch().unwrap();
+        let timestamps = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<TimestampMillisecondArray>()
+            .unwrap();
+        assert_eq!(timestamps.value(0), 1_672_606_800_000);
+
+        let buffer = events.write_parquet(Vec::new()).unwrap();
+        assert!(buffer.starts_with(b"PAR1"));
+
+        let cycles: [ChargeCycle; 0] = [];
+        assert_eq!(cycles.to_record_batch().unwrap().num_rows(), 0);
+    }
+}
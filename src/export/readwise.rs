@@ -0,0 +1,101 @@
+use serde::Serialize;
+
+use crate::export::ExportError;
+use crate::model::{parse_kobo_date, Book, Bookmark};
+
+#[derive(Serialize)]
+struct ReadwiseRow<'a> {
+    #[serde(rename = "Highlight")]
+    highlight: &'a str,
+    #[serde(rename = "Title")]
+    title: &'a str,
+    #[serde(rename = "Author")]
+    author: &'a str,
+    #[serde(rename = "URL")]
+    url: &'a str,
+    #[serde(rename = "Note")]
+    note: &'a str,
+    #[serde(rename = "Location")]
+    location: u64,
+    #[serde(rename = "Date")]
+    date: String,
+}
+
+pub fn readwise_location(bookmark: &Bookmark) -> u64 {
+    (bookmark.position() * 100.0).round() as u64
+}
+
+pub fn readwise_date(value: &str) -> String {
+    parse_kobo_date(value)
+        .map(|ts| ts.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|| value.trim().to_string())
+}
+
+pub fn to_readwise_csv(bookmarks: &[Bookmark], books: &[Book]) -> Result<String, ExportError> {
+    let mut wtr = csv::Writer::from_writer(vec![]);
+    for bookmark in bookmarks {
+        let book = bookmark.find_book(books);
+        let title = book
+            .map(|book| book.title.as_str())
+            .filter(|title| !title.is_empty())
+            .unwrap_or(&bookmark.book_title);
+        wtr.serialize(ReadwiseRow {
+            highlight: bookmark.content.trim(),
+            title,
+            author: book.map(|book| book.authors.as_str()).unwrap_or_default(),
+            url: "",
+            note: bookmark.annotation.as_deref().unwrap_or_default(),
+            location: readwise_location(bookmark),
+            date: readwise_date(&bookmark.create_date),
+        })?;
+    }
+    Ok(String::from_utf8(wtr.into_inner()?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bookmark(chapter_index: Option<u32>, progress: f64, date: &str) -> Bookmark {
+        Bookmark {
+            content_id: "bm1".to_string(),
+            content: " Fear is the mind-killer. ".to_string(),
+            book_id: "volume1".to_string(),
+            book_title: "Dune".to_string(),
+            color: 0,
+            chapter_progress: progress,
+            create_date: date.to_string(),
+            write_date: date.to_string(),
+            annotation: Some("Litany".to_string()),
+            chapter_index,
+        }
+    }
+
+    #[test]
+    fn test_readwise_csv_layout() {
+        let books = [Book::new(
+            "Frank Herbert".to_string(),
+            "Dune".to_string(),
+            None,
+            "volume1".to_string(),
+        )];
+        let bookmarks = [
+            bookmark(Some(3), 0.456, "2023-01-01T10:06:00.000"),
+            bookmark(None, 0.25, "2023-01-02 08:00:00"),
+        ];
+        let expected = [
+            "Highlight,Title,Author,URL,Note,Location,Date",
+            "Fear is the mind-killer.,Dune,Frank Herbert,,Litany,346,2023-01-01 10:06:00",
+            "Fear is the mind-killer.,Dune,Frank Herbert,,Litany,25,2023-01-02 08:00:00",
+            "",
+        ]
+        .join("\n");
+        assert_eq!(to_readwise_csv(&bookmarks, &books).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_readwise_without_book_metadata() {
+        let csv = to_readwise_csv(&[bookmark(Some(1), 1.0, "garbage")], &[]).unwrap();
+        assert!(csv.ends_with("Dune,,,Litany,200,garbage\n"));
+    }
+}
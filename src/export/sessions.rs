@@ -1,12 +1,12 @@
-use chrono::{DateTime, SecondsFormat, Utc};
 use serde::Serialize;
 
-use crate::export::{Export, ExportError};
+use crate::export::{format_time, Export, ExportError};
 use crate::model::CorrelatedSession;
 
 #[derive(Serialize)]
 struct SessionExportRow {
     session_id: String,
+    completeness: &'static str,
     open_content_id: String,
     leave_content_id: Option<String>,
     start_time: String,
@@ -24,10 +24,7 @@ struct SessionExportRow {
     brightness_events: usize,
     natural_light_events: usize,
     app_events: usize,
-}
-
-fn format_time(ts: DateTime<Utc>) -> String {
-    ts.to_rfc3339_opts(SecondsFormat::Secs, true)
+    annotations: usize,
 }
 
 fn to_export_row(session: &CorrelatedSession) -> SessionExportRow {
@@ -35,6 +32,7 @@ fn to_export_row(session: &CorrelatedSession) -> SessionExportRow {
     let progress_delta = end_progress.map(|end| end as i16 - session.session.start_progress as i16);
     SessionExportRow {
         session_id: session.session.id.to_string(),
+        completeness: session.session.completeness.as_str(),
         open_content_id: session.session.open_content_id.clone(),
         leave_content_id: session.session.leave_content_id.clone(),
         start_time: format_time(session.session.time_start),
@@ -52,6 +50,7 @@ fn to_export_row(session: &CorrelatedSession) -> SessionExportRow {
         brightness_events: session.brightness.len(),
         natural_light_events: session.natural_light.len(),
         app_events: session.app_events.len(),
+        annotations: session.annotations.len(),
     }
 }
 
@@ -70,11 +69,11 @@ impl Export for [CorrelatedSession] {
 
         writeln!(
             buffer,
-            "| Start | End | Progress Δ | Pages | Buttons | Book | Dictionary | Brightness |"
+            "| Start | End | Progress Δ | Pages | Buttons | Book | Dictionary | Brightness | Annotations |"
         )?;
         writeln!(
             buffer,
-            "|-------|-----|------------|-------|---------|------|------------|------------|"
+            "|-------|-----|------------|-------|---------|------|------------|------------|-------------|"
         )?;
 
         for session in self {
@@ -97,7 +96,7 @@ impl Export for [CorrelatedSession] {
 
             writeln!(
                 buffer,
-                "| {} | {} | {} | {} | {} | {} | {} | {} |",
+                "| {} | {} | {} | {} | {} | {} | {} | {} | {} |",
                 row.start_time,
                 end_time,
                 progress_delta,
@@ -105,7 +104,8 @@ impl Export for [CorrelatedSession] {
                 buttons,
                 book_title,
                 row.dictionary_lookups,
-                brightness_count
+                brightness_count,
+                row.annotations
             )?;
         }
 
@@ -122,8 +122,8 @@ impl Export for [CorrelatedSession] {
 mod tests {
     use super::*;
     use crate::{
-        AppEvent, AppEventKind, Brightness, BrightnessEvent, CorrelatedSession, DictionaryWord,
-        ReadingSession,
+        AppEvent, AppEventKind, Bookmark, Brightness, BrightnessEvent, CorrelatedSession,
+        DictionaryWord, ReadingSession,
     };
     use chrono::{DateTime, Utc};
     use std::str::FromStr;
@@ -160,6 +160,18 @@ mod tests {
         correlated
             .app_events
             .push(AppEvent::new(AppEventKind::AppStart, start, None));
+        correlated.annotations.push(Bookmark {
+            content_id: "bookmark1".to_string(),
+            content: "highlight".to_string(),
+            book_id: "book1".to_string(),
+            book_title: "Book Title".to_string(),
+            color: 1,
+            chapter_progress: 0.5,
+            create_date: "2023-01-01T10:05:00Z".to_string(),
+            write_date: "2023-01-01T10:05:00Z".to_string(),
+            annotation: None,
+            chapter_index: None,
+        });
         correlated
     }
 
@@ -167,8 +179,8 @@ mod tests {
     fn test_sessions_to_csv() {
         let sessions = [build_session()];
         let expected = [
-            "session_id,open_content_id,leave_content_id,start_time,end_time,duration_seconds,start_progress,end_progress,progress_delta,seconds_read,pages_turned,button_press_count,book_title,volume_id,dictionary_lookups,brightness_events,natural_light_events,app_events",
-            "00000000-0000-0000-0000-000000000000,open1,leave1,2023-01-01T10:00:00Z,2023-01-01T10:10:00Z,600,10,20,10,600,5,3,Book Title,book1,1,1,1,1",
+            "session_id,completeness,open_content_id,leave_content_id,start_time,end_time,duration_seconds,start_progress,end_progress,progress_delta,seconds_read,pages_turned,button_press_count,book_title,volume_id,dictionary_lookups,brightness_events,natural_light_events,app_events,annotations",
+            "00000000-0000-0000-0000-000000000000,complete,open1,leave1,2023-01-01T10:00:00Z,2023-01-01T10:10:00Z,600,10,20,10,600,5,3,Book Title,book1,1,1,1,1,1",
             "",
         ]
         .join("\n");
@@ -181,5 +193,14 @@ mod tests {
         let json = sessions.to_json().unwrap();
         assert!(json.contains("\"session_id\":\"00000000-0000-0000-0000-000000000000\""));
         assert!(json.contains("\"dictionary_lookups\":1"));
+        assert!(json.contains("\"annotations\":1"));
+    }
+
+    #[test]
+    fn test_sessions_to_md() {
+        let sessions = [build_session()];
+        let md = sessions.to_md().unwrap();
+        assert!(md.lines().next().unwrap().ends_with("| Annotations |"));
+        assert!(md.contains("| Book Title | 1 | 2 | 1 |"));
     }
 }
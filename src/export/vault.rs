@@ -0,0 +1,537 @@
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+use crate::export::ExportError;
+use crate::model::{Book, Bookmark, ReadingSessions};
+
+pub const VAULT_MARKER: &str = "<!-- kobo-db-tools: your notes below this line are kept -->";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VaultFlavor {
+    #[default]
+    Obsidian,
+    Logseq,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VaultNote {
+    pub file_name: String,
+    pub content: String,
+}
+
+pub struct NoteVault<'a> {
+    bookmarks: &'a [Bookmark],
+    books: &'a [Book],
+    sessions: Option<&'a ReadingSessions>,
+    flavor: VaultFlavor,
+}
+
+struct ReadingDates {
+    started: DateTime<Utc>,
+    last_read: DateTime<Utc>,
+    seconds_read: u64,
+}
+
+fn yaml_string(value: &str) -> String {
+    let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+    format!("\"{}\"", escaped)
+}
+
+fn file_name(title: &str) -> String {
+    let name: String = title
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' | '#' | '^' | '[' | ']' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    let name = name.trim().trim_matches('.');
+    if name.is_empty() {
+        "Untitled.md".to_string()
+    } else {
+        format!("{}.md", name)
+    }
+}
+
+fn unique_file_name(file_name: &str, volume_id: &str) -> String {
+    let stem = file_name.strip_suffix(".md").unwrap_or(file_name);
+    let hash = Uuid::new_v5(&Uuid::NAMESPACE_OID, volume_id.as_bytes()).simple();
+    format!("{} ({}).md", stem, &hash.to_string()[..8])
+}
+
+pub fn block_id(bookmark: &Bookmark) -> Uuid {
+    Uuid::parse_str(&bookmark.content_id)
+        .unwrap_or_else(|_| Uuid::new_v5(&Uuid::NAMESPACE_OID, bookmark.content_id.as_bytes()))
+}
+
+impl<'a> NoteVault<'a> {
+    pub fn new(bookmarks: &'a [Bookmark], books: &'a [Book]) -> Self {
+        Self {
+            bookmarks,
+            books,
+            sessions: None,
+            flavor: VaultFlavor::Obsidian,
+        }
+    }
+
+    pub fn with_sessions(mut self, sessions: &'a ReadingSessions) -> Self {
+        self.sessions = Some(sessions);
+        self
+    }
+
+    pub fn with_flavor(mut self, flavor: VaultFlavor) -> Self {
+        self.flavor = flavor;
+        self
+    }
+
+    fn reading_dates(&self, volume_id: &str, title: &str) -> Option<ReadingDates> {
+        let sessions = self.sessions?.get_sessions().iter().filter(|session| {
+            match session.volume_id.as_deref() {
+                Some(session_volume) => session_volume == volume_id,
+                None => session.book_title.as_deref() == Some(title),
+            }
+        });
+        let mut dates: Option<ReadingDates> = None;
+        for session in sessions {
+            let end = session.time_end.unwrap_or(session.time_start);
+            let seconds = session.seconds_read.unwrap_or(0);
+            match dates.as_mut() {
+                Some(dates) => {
+                    dates.started = dates.started.min(session.time_start);
+                    dates.last_read = dates.last_read.max(end);
+                    dates.seconds_read += seconds;
+                }
+                None => {
+                    dates = Some(ReadingDates {
+                        started: session.time_start,
+                        last_read: end,
+                        seconds_read: seconds,
+                    })
+                }
+            }
+        }
+        dates
+    }
+
+    pub fn notes(&self) -> Result<Vec<VaultNote>, ExportError> {
+        let mut groups: Vec<(&str, Vec<&Bookmark>)> = Vec::new();
+        for bookmark in self.bookmarks {
+            match groups
+                .iter_mut()
+                .find(|(volume_id, _)| *volume_id == bookmark.book_id)
+            {
+                Some((_, bookmarks)) => bookmarks.push(bookmark),
+                None => groups.push((&bookmark.book_id, vec![bookmark])),
+            }
+        }
+
+        let mut notes = Vec::with_capacity(groups.len());
+        for (volume_id, mut bookmarks) in groups {
+            bookmarks.sort_by(|a, b| {
+                a.position()
+                    .total_cmp(&b.position())
+                    .then_with(|| a.create_date.cmp(&b.create_date))
+            });
+            let book = bookmarks[0].find_book(self.books);
+            let title = book
+                .map(|book| book.title.as_str())
+                .filter(|title| !title.is_empty())
+                .unwrap_or(bookmarks[0].book_title.as_str());
+            let title = if title.is_empty() { volume_id } else { title };
+            notes.push((
+                volume_id,
+                VaultNote {
+                    file_name: file_name(title),
+                    content: self.render(volume_id, title, book, &bookmarks)?,
+                },
+            ));
+        }
+
+        let mut name_counts: HashMap<String, usize> = HashMap::new();
+        for (_, note) in &notes {
+            *name_counts
+                .entry(note.file_name.to_lowercase())
+                .or_default() += 1;
+        }
+        let mut notes: Vec<VaultNote> = notes
+            .into_iter()
+            .map(|(volume_id, mut note)| {
+                if name_counts[&note.file_name.to_lowercase()] > 1 {
+                    note.file_name = unique_file_name(&note.file_name, volume_id);
+                }
+                note
+            })
+            .collect();
+        notes.sort_by(|a, b| a.file_name.cmp(&b.file_name));
+        Ok(notes)
+    }
+
+    fn render(
+        &self,
+        volume_id: &str,
+        title: &str,
+        book: Option<&Book>,
+        bookmarks: &[&Bookmark],
+    ) -> Result<String, ExportError> {
+        let mut buffer = Vec::new();
+        writeln!(buffer, "---")?;
+        writeln!(buffer, "title: {}", yaml_string(title))?;
+        if let Some(book) = book {
+            let authors: Vec<&str> = book
+                .authors
+                .split(['&', ','])
+                .map(str::trim)
+                .filter(|author| !author.is_empty())
+                .collect();
+            if !authors.is_empty() {
+                writeln!(buffer, "authors:")?;
+                for author in authors {
+                    writeln!(buffer, "  - {}", yaml_string(author))?;
+                }
+            }
+            if let Some(isbn) = book.isbn() {
+                writeln!(buffer, "isbn: {}", yaml_string(isbn))?;
+            }
+            if let Some(series) = book.series() {
+                writeln!(buffer, "series: {}", yaml_string(series))?;
+            }
+            if let Some(number) = &book.series_number {
+                writeln!(buffer, "series_number: {}", yaml_string(number))?;
+            }
+        }
+        if let Some(dates) = self.reading_dates(volume_id, title) {
+            writeln!(buffer, "started: {}", dates.started.format("%Y-%m-%d"))?;
+            writeln!(buffer, "last_read: {}", dates.last_read.format("%Y-%m-%d"))?;
+            writeln!(buffer, "reading_minutes: {}", dates.seconds_read / 60)?;
+        }
+        writeln!(buffer, "highlights: {}", bookmarks.len())?;
+        writeln!(buffer, "kobo_volume_id: {}", yaml_string(volume_id))?;
+        writeln!(buffer, "---")?;
+        writeln!(buffer)?;
+
+        match self.flavor {
+            VaultFlavor::Obsidian => {
+                writeln!(buffer, "# {}", title)?;
+                for bookmark in bookmarks {
+                    writeln!(buffer)?;
+                    for line in bookmark.content.trim().lines() {
+                        writeln!(buffer, "> {}", line)?;
+                    }
+                    writeln!(buffer, ">")?;
+                    writeln!(
+                        buffer,
+                        "> *{:.0}% · {} · {}*",
+                        bookmark.chapter_progress * 100.0,
+                        bookmark.color_name(),
+                        bookmark.create_date
+                    )?;
+                    if let Some(note) = &bookmark.annotation {
+                        writeln!(buffer, ">")?;
+                        writeln!(buffer, "> **Note:** {}", note.trim())?;
+                    }
+                    writeln!(buffer)?;
+                    writeln!(buffer, "^kobo-{}", block_id(bookmark))?;
+                }
+            }
+            VaultFlavor::Logseq => {
+                for bookmark in bookmarks {
+                    let text = bookmark.content.trim().replace('\n', " ");
+                    writeln!(buffer, "- > {}", text)?;
+                    writeln!(buffer, "  id:: {}", block_id(bookmark))?;
+                    writeln!(buffer, "  color:: {}", bookmark.color_name())?;
+                    writeln!(
+                        buffer,
+                        "  progress:: {:.0}%",
+                        bookmark.chapter_progress * 100.0
+                    )?;
+                    writeln!(buffer, "  created:: {}", bookmark.create_date)?;
+                    if let Some(note) = &bookmark.annotation {
+                        writeln!(buffer, "\t- {}", note.trim().replace('\n', " "))?;
+                    }
+                }
+            }
+        }
+        writeln!(buffer)?;
+        writeln!(buffer, "{}", VAULT_MARKER)?;
+        Ok(String::from_utf8(buffer)?)
+    }
+
+    pub fn write_to<P: AsRef<Path>>(&self, dir: P) -> Result<Vec<PathBuf>, ExportError> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+        let notes = self.notes()?;
+        let claimed: HashSet<&str> = notes.iter().map(|note| note.file_name.as_str()).collect();
+        let mut written = Vec::new();
+        for note in &notes {
+            let path = dir.join(&note.file_name);
+            let (existing, moved_from) = match fs::read_to_string(&path) {
+                Ok(existing) => (Some(existing), None),
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                    match moved_note(dir, &note.content, &claimed)? {
+                        Some((old_path, existing)) => (Some(existing), Some(old_path)),
+                        None => (None, None),
+                    }
+                }
+                Err(err) => return Err(err.into()),
+            };
+            let content = match existing {
+                Some(existing) => merge(&note.content, &existing),
+                None => note.content.clone(),
+            };
+            fs::write(&path, content)?;
+            if let Some(old_path) = moved_from {
+                fs::remove_file(old_path)?;
+            }
+            written.push(path);
+        }
+        Ok(written)
+    }
+}
+
+fn volume_line(content: &str) -> Option<&str> {
+    content
+        .split_once(VAULT_MARKER)
+        .map_or(content, |(head, _)| head)
+        .lines()
+        .find(|line| line.starts_with("kobo_volume_id: "))
+}
+
+fn moved_note(
+    dir: &Path,
+    generated: &str,
+    claimed: &HashSet<&str>,
+) -> Result<Option<(PathBuf, String)>, ExportError> {
+    let Some(line) = volume_line(generated) else {
+        return Ok(None);
+    };
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        if !name.ends_with(".md") || claimed.contains(name) {
+            continue;
+        }
+        let existing = fs::read_to_string(&path)?;
+        if volume_line(&existing) == Some(line) {
+            return Ok(Some((path, existing)));
+        }
+    }
+    Ok(None)
+}
+
+pub fn merge(generated: &str, existing: &str) -> String {
+    match existing.split_once(VAULT_MARKER) {
+        Some((_, user_content)) => {
+            let generated = generated
+                .split_once(VAULT_MARKER)
+                .map_or(generated, |(head, _)| head);
+            format!("{}{}{}", generated, VAULT_MARKER, user_content)
+        }
+        None => generated.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ReadingSession;
+    use std::str::FromStr;
+
+    fn bookmark(id: &str, text: &str, progress: f64, note: Option<&str>) -> Bookmark {
+        Bookmark {
+            content_id: id.to_string(),
+            content: text.to_string(),
+            book_id: "volume1".to_string(),
+            book_title: "Dune".to_string(),
+            color: 2,
+            chapter_progress: progress,
+            create_date: "2023-01-01T10:00:00Z".to_string(),
+            write_date: "2023-01-01T10:00:00Z".to_string(),
+            annotation: note.map(str::to_string),
+            chapter_index: None,
+        }
+    }
+
+    fn build() -> (Vec<Bookmark>, Vec<Book>, ReadingSessions) {
+        let bookmarks = vec![
+            bookmark("bm-2", "Second", 0.8, None),
+            bookmark(
+                "5a4c2e44-34cb-4a6f-9f39-4cc3c1a3f0f1",
+                "First",
+                0.1,
+                Some("why?"),
+            ),
+        ];
+        let mut book = Book::new(
+            "Frank Herbert".to_string(),
+            "Dune".to_string(),
+            None,
+            "volume1".to_string(),
+        );
+        book.isbn = Some("9780441013593".to_string());
+        book.series = Some("Dune".to_string());
+        let mut sessions = ReadingSessions::new();
+        let start = DateTime::<Utc>::from_str("2023-01-01T09:00:00Z").unwrap();
+        let mut session = ReadingSession::new(
+            start,
+            0,
+            Some("Dune".to_string()),
+            Some("volume1".to_string()),
+            "open1".to_string(),
+        );
+        session
+            .complete_session(
+                DateTime::<Utc>::from_str("2023-01-02T10:00:00Z").unwrap(),
+                20,
+                0,
+                5400,
+                30,
+                "leave1".to_string(),
+            )
+            .unwrap();
+        sessions.add_session(session);
+        (bookmarks, vec![book], sessions)
+    }
+
+    #[test]
+    fn test_obsidian_note() {
+        let (bookmarks, books, sessions) = build();
+        let notes = NoteVault::new(&bookmarks, &books)
+            .with_sessions(&sessions)
+            .notes()
+            .unwrap();
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes[0].file_name, "Dune.md");
+        let content = &notes[0].content;
+        assert!(content.starts_with("---\ntitle: \"Dune\"\nauthors:\n  - \"Frank Herbert\"\nisbn: \"9780441013593\"\nseries: \"Dune\"\nstarted: 2023-01-01\nlast_read: 2023-01-02\nreading_minutes: 90\n"));
+        let first = content.find("> First").unwrap();
+        let second = content.find("> Second").unwrap();
+        assert!(first < second);
+        assert!(content.contains("> *10% · blue · 2023-01-01T10:00:00Z*\n>\n> **Note:** why?\n\n^kobo-5a4c2e44-34cb-4a6f-9f39-4cc3c1a3f0f1\n"));
+        assert!(content.ends_with(&format!("{}\n", VAULT_MARKER)));
+    }
+
+    #[test]
+    fn test_logseq_note() {
+        let (bookmarks, books, _) = build();
+        let notes = NoteVault::new(&bookmarks, &books)
+            .with_flavor(VaultFlavor::Logseq)
+            .notes()
+            .unwrap();
+        let content = &notes[0].content;
+        assert!(content
+            .contains("- > First\n  id:: 5a4c2e44-34cb-4a6f-9f39-4cc3c1a3f0f1\n  color:: blue\n"));
+        assert!(content.contains("\t- why?\n"));
+        assert!(!content.contains("started:"));
+    }
+
+    #[test]
+    fn test_shared_titles_get_unique_names() {
+        let (mut bookmarks, books, _) = build();
+        let mut other = bookmark("bm-other", "Other edition", 0.5, None);
+        other.book_id = "volume2".to_string();
+        bookmarks.push(other);
+        let notes = NoteVault::new(&bookmarks, &books).notes().unwrap();
+        assert_eq!(notes.len(), 2);
+        assert_ne!(notes[0].file_name, notes[1].file_name);
+        for note in &notes {
+            assert!(note.file_name.starts_with("Dune ("));
+            assert!(note.file_name.ends_with(").md"));
+        }
+        let other = notes
+            .iter()
+            .find(|note| note.content.contains("> Other edition"))
+            .unwrap();
+        assert!(!other.content.contains("> First"));
+
+        let dir = std::env::temp_dir().join(format!("kobo-vault-{}", Uuid::new_v4()));
+        assert_eq!(
+            NoteVault::new(&bookmarks, &books)
+                .write_to(&dir)
+                .unwrap()
+                .len(),
+            2
+        );
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_new_edition_keeps_existing_note() {
+        let (mut bookmarks, books, mut sessions) = build();
+        let dir = std::env::temp_dir().join(format!("kobo-vault-{}", Uuid::new_v4()));
+        let path = NoteVault::new(&bookmarks, &books).write_to(&dir).unwrap()[0].clone();
+        assert!(path.ends_with("Dune.md"));
+        let mut edited = fs::read_to_string(&path).unwrap();
+        edited.push_str("\nMy own thoughts.\n");
+        fs::write(&path, edited).unwrap();
+
+        let mut other = bookmark("bm-other", "Other edition", 0.5, None);
+        other.book_id = "volume2".to_string();
+        bookmarks.push(other);
+        let mut reread = ReadingSession::new(
+            DateTime::<Utc>::from_str("2024-05-01T09:00:00Z").unwrap(),
+            0,
+            Some("Dune".to_string()),
+            Some("volume2".to_string()),
+            "open2".to_string(),
+        );
+        reread
+            .complete_session(
+                DateTime::<Utc>::from_str("2024-05-01T10:00:00Z").unwrap(),
+                5,
+                0,
+                3600,
+                10,
+                "leave2".to_string(),
+            )
+            .unwrap();
+        sessions.add_session(reread);
+
+        let written = NoteVault::new(&bookmarks, &books)
+            .with_sessions(&sessions)
+            .write_to(&dir)
+            .unwrap();
+        assert_eq!(written.len(), 2);
+        assert!(!path.exists());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+        for written in written {
+            let content = fs::read_to_string(&written).unwrap();
+            if content.contains("> First") {
+                assert!(content.ends_with("My own thoughts.\n"));
+                assert!(content.contains("started: 2023-01-01\n"));
+            } else {
+                assert!(!content.contains("My own thoughts."));
+                assert!(content
+                    .contains("started: 2024-05-01\nlast_read: 2024-05-01\nreading_minutes: 60\n"));
+            }
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_write_keeps_user_content() {
+        let (mut bookmarks, books, _) = build();
+        let dir = std::env::temp_dir().join(format!("kobo-vault-{}", Uuid::new_v4()));
+        let path = NoteVault::new(&bookmarks, &books).write_to(&dir).unwrap()[0].clone();
+
+        let mut edited = fs::read_to_string(&path).unwrap();
+        edited.push_str("\nMy own thoughts.\n");
+        fs::write(&path, edited).unwrap();
+
+        bookmarks.push(bookmark("bm-3", "Third", 0.9, None));
+        NoteVault::new(&bookmarks, &books).write_to(&dir).unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.contains("> Third"));
+        assert!(content.contains("highlights: 3"));
+        assert!(content.ends_with(&format!("{}\n\nMy own thoughts.\n", VAULT_MARKER)));
+        assert_eq!(content.matches(VAULT_MARKER).count(), 1);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,9 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ImportError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("serde_json::from_str error: {0}")]
+    JsonFromStr(#[from] serde_json::Error),
+}
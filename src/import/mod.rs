@@ -0,0 +1,153 @@
+pub mod error;
+
+use serde::de::DeserializeOwned;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use crate::{CorrelatedAnalysis, EventAnalysis};
+use error::ImportError;
+
+pub trait Import: Sized {
+    fn from_json(json: &str) -> Result<Self, ImportError>;
+    fn from_json_reader<R: Read>(reader: R) -> Result<Self, ImportError>;
+
+    fn from_json_file<P: AsRef<Path>>(path: P) -> Result<Self, ImportError> {
+        let file = File::open(path)?;
+        Self::from_json_reader(BufReader::new(file))
+    }
+}
+
+fn from_json<T: DeserializeOwned>(json: &str) -> Result<T, ImportError> {
+    Ok(serde_json::from_str(json)?)
+}
+
+fn from_json_reader<T: DeserializeOwned, R: Read>(reader: R) -> Result<T, ImportError> {
+    Ok(serde_json::from_reader(reader)?)
+}
+
+impl Import for EventAnalysis {
+    fn from_json(json: &str) -> Result<Self, ImportError> {
+        from_json(json)
+    }
+
+    fn from_json_reader<R: Read>(reader: R) -> Result<Self, ImportError> {
+        from_json_reader(reader)
+    }
+}
+
+impl Import for CorrelatedAnalysis {
+    fn from_json(json: &str) -> Result<Self, ImportError> {
+        from_json(json)
+    }
+
+    fn from_json_reader<R: Read>(reader: R) -> Result<Self, ImportError> {
+        from_json_reader(reader)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        AppEvent, AppEventKind, Brightness, BrightnessEvent, BrightnessHistory, ChargeCycle,
+        ChargeCycleMetrics, CorrelatedSession, DictionaryWord, OrphanEvents, ReadingSession,
+        ReadingSessions, SessionCompleteness,
+    };
+    use chrono::{DateTime, NaiveDate, Utc};
+    use serde_json::json;
+    use std::collections::HashMap;
+    use std::str::FromStr;
+
+    fn ts(value: &str) -> DateTime<Utc> {
+        DateTime::<Utc>::from_str(value).unwrap()
+    }
+
+    fn build_session() -> ReadingSession {
+        let mut session = ReadingSession::new(
+            ts("2023-01-01T10:00:00Z"),
+            5,
+            Some("Book".to_string()),
+            Some("book1".to_string()),
+            "open1".to_string(),
+        );
+        session
+            .complete_session(ts("2023-01-01T10:30:00Z"), 20, 4, 1800, 12, "leave1".into())
+            .unwrap();
+        session
+    }
+
+    #[test]
+    fn test_event_analysis_round_trip() {
+        let session = build_session();
+        let word = DictionaryWord::new("ephemeral".into(), "en".into(), Some(session.id));
+        let mut sessions = ReadingSessions::new();
+        sessions.add_session(session);
+        let mut brightness = BrightnessHistory::new();
+        brightness.insert(BrightnessEvent::new(
+            Brightness::new("Manual".into(), 40),
+            ts("2023-01-01T10:05:00Z"),
+        ));
+        let analysis = EventAnalysis {
+            sessions: Some(sessions),
+            terms: Some(HashMap::from([(word, 2)])),
+            brightness_history: Some(brightness),
+            ..Default::default()
+        };
+
+        let json = serde_json::to_string(&analysis).unwrap();
+        let imported = EventAnalysis::from_json(&json).unwrap();
+        assert_eq!(serde_json::to_string(&imported).unwrap(), json);
+
+        let sessions = imported.sessions.unwrap();
+        assert_eq!(sessions.get_sessions()[0].id, build_session().id);
+        assert_eq!(
+            sessions.get_sessions()[0].completeness,
+            SessionCompleteness::Complete
+        );
+        let (word, count) = imported.terms.unwrap().into_iter().next().unwrap();
+        assert_eq!(word.term(), "ephemeral");
+        assert_eq!(count, 2);
+        assert!(imported.bookmarks.is_none());
+    }
+
+    #[test]
+    fn test_correlated_analysis_round_trip() {
+        let mut session = CorrelatedSession::new(build_session());
+        session.app_events.push(AppEvent::new(
+            AppEventKind::AppStart,
+            ts("2023-01-01T09:59:50Z"),
+            Some(json!({"battery": 80})),
+        ));
+        let analysis = CorrelatedAnalysis {
+            sessions: vec![session],
+            orphans: OrphanEvents::default(),
+            cycles: vec![ChargeCycle {
+                start: ts("2023-01-01T08:00:00Z"),
+                end: None,
+                session_indices: vec![0],
+                app_events: Vec::new(),
+                metrics: ChargeCycleMetrics {
+                    total_seconds_read: 1800,
+                    start_battery: Some(80),
+                    ..Default::default()
+                },
+            }],
+            app_start_counts_by_day: HashMap::from([(
+                NaiveDate::from_ymd_opt(2023, 1, 1).unwrap(),
+                1,
+            )]),
+        };
+
+        let json = serde_json::to_string(&analysis).unwrap();
+        let imported = CorrelatedAnalysis::from_json_reader(json.as_bytes()).unwrap();
+        assert_eq!(serde_json::to_string(&imported).unwrap(), json);
+        assert_eq!(imported.cycle_sessions(&imported.cycles[0]).count(), 1);
+        assert_eq!(imported.sessions[0].app_events[0].battery_level(), Some(80));
+
+        assert!(matches!(
+            CorrelatedAnalysis::from_json("{}"),
+            Err(ImportError::JsonFromStr(_))
+        ));
+    }
+}
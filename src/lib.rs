@@ -1,10 +1,18 @@
+pub mod backup;
+pub mod correlation;
 pub mod db;
+pub mod device;
 pub mod export;
+pub mod import;
 pub mod model;
 pub mod parser;
+pub mod schema;
 pub mod statistics;
 
+pub use correlation::{CorrelationConfig, MatchStrategy};
 pub use db::*;
+pub use device::{DeviceDb, DeviceDbError, WritableDeviceDb};
 pub use model::*;
 pub use parser::{EventAnalysis, ParseError, ParseOption, Parser};
+pub use schema::{DbSchema, Feature, FeatureReport, FeatureStatus};
 pub use statistics::*;
@@ -1,18 +1,32 @@
 use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+const BATTERY_KEYS: [&str; 3] = ["batterylevel", "battery", "batterypercentage"];
+const CHARGER_KEYS: [&str; 3] = ["chargertype", "charger", "powersource"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum AppEventKind {
     AppStart,
     PluggedIn,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppEvent {
     pub kind: AppEventKind,
     pub timestamp: DateTime<Utc>,
     pub attributes: Option<serde_json::Value>,
 }
 
+impl AppEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AppEventKind::AppStart => "AppStart",
+            AppEventKind::PluggedIn => "PluggedIn",
+        }
+    }
+}
+
 impl AppEvent {
     pub fn new(
         kind: AppEventKind,
@@ -25,4 +39,67 @@ impl AppEvent {
             attributes,
         }
     }
+
+    fn attribute(&self, keys: &[&str]) -> Option<&Value> {
+        let object = self.attributes.as_ref()?.as_object()?;
+        keys.iter().find_map(|key| {
+            object
+                .iter()
+                .find(|(name, _)| name.to_ascii_lowercase() == *key)
+                .map(|(_, value)| value)
+        })
+    }
+
+    pub fn battery_level(&self) -> Option<u8> {
+        let level = match self.attribute(&BATTERY_KEYS)? {
+            Value::Number(number) => number.as_f64()?,
+            Value::String(text) => text.trim().trim_end_matches('%').trim().parse().ok()?,
+            _ => return None,
+        };
+        (0.0..=100.0)
+            .contains(&level)
+            .then_some(level.round() as u8)
+    }
+
+    pub fn charger(&self) -> Option<String> {
+        match self.attribute(&CHARGER_KEYS)? {
+            Value::String(text) if !text.trim().is_empty() => Some(text.trim().to_string()),
+            Value::Number(number) => Some(number.to_string()),
+            Value::Bool(flag) => Some(flag.to_string()),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_battery_and_charger_attributes() {
+        let ts = Utc::now();
+        let plugged = AppEvent::new(
+            AppEventKind::PluggedIn,
+            ts,
+            Some(json!({"BatteryLevel": "42%", "ChargerType": "USB"})),
+        );
+        assert_eq!(plugged.battery_level(), Some(42));
+        assert_eq!(plugged.charger().as_deref(), Some("USB"));
+
+        let start = AppEvent::new(AppEventKind::AppStart, ts, Some(json!({"battery": 87})));
+        assert_eq!(start.battery_level(), Some(87));
+        assert_eq!(start.charger(), None);
+
+        let generic = AppEvent::new(
+            AppEventKind::AppStart,
+            ts,
+            Some(json!({"level": 3, "source": "menu"})),
+        );
+        assert_eq!(generic.battery_level(), None);
+        assert_eq!(generic.charger(), None);
+
+        let empty = AppEvent::new(AppEventKind::AppStart, ts, None);
+        assert_eq!(empty.battery_level(), None);
+    }
 }
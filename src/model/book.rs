@@ -1,4 +1,11 @@
+use rusqlite::{Connection, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::schema::DbSchema;
+use crate::ReadingSession;
+
+pub const READ_STATUS_FINISHED: u8 = 2;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Book {
@@ -6,6 +13,16 @@ pub struct Book {
     pub title: String,
     pub size: Option<u64>,
     pub book_id: String,
+    #[serde(default)]
+    pub isbn: Option<String>,
+    #[serde(default)]
+    pub series: Option<String>,
+    #[serde(default)]
+    pub series_number: Option<String>,
+    #[serde(default)]
+    pub read_status: Option<u8>,
+    #[serde(default)]
+    pub shelves: Vec<String>,
 }
 
 impl Book {
@@ -15,6 +32,11 @@ impl Book {
             title,
             size,
             book_id,
+            isbn: None,
+            series: None,
+            series_number: None,
+            read_status: None,
+            shelves: Vec::new(),
         }
     }
 
@@ -29,4 +51,87 @@ impl Book {
     pub fn size(&self) -> Option<u64> {
         self.size
     }
+
+    pub fn isbn(&self) -> Option<&str> {
+        self.isbn.as_deref()
+    }
+
+    pub fn series(&self) -> Option<&str> {
+        self.series.as_deref()
+    }
+
+    pub fn matches_session(&self, session: &ReadingSession) -> bool {
+        (!self.book_id.is_empty() && session.volume_id.as_deref() == Some(self.book_id.as_str()))
+            || (!self.title.is_empty()
+                && session.book_title.as_deref() == Some(self.title.as_str()))
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.read_status == Some(READ_STATUS_FINISHED)
+    }
+}
+
+pub fn get_shelves(db: &Connection) -> Result<HashMap<String, Vec<String>>> {
+    get_shelves_with(db, &DbSchema::detect(db)?)
+}
+
+pub fn get_shelves_with(
+    db: &Connection,
+    schema: &DbSchema,
+) -> Result<HashMap<String, Vec<String>>> {
+    let mut shelves: HashMap<String, Vec<String>> = HashMap::new();
+    if !schema.has_column("ShelfContent", "ContentId")
+        || !schema.has_column("ShelfContent", "ShelfName")
+    {
+        return Ok(shelves);
+    }
+    let query = format!(
+        "SELECT ContentId, ShelfName FROM ShelfContent
+         WHERE COALESCE({}, 'false') NOT IN ('true', '1')
+         ORDER BY ShelfName",
+        if schema.has_column("ShelfContent", "_IsDeleted") {
+            "_IsDeleted"
+        } else {
+            "NULL"
+        }
+    );
+    let mut stmt = db.prepare(&query)?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let content_id: String = row.get("ContentId")?;
+        let shelf: String = row.get("ShelfName")?;
+        shelves.entry(content_id).or_default().push(shelf);
+    }
+    Ok(shelves)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_shelves() {
+        let db = Connection::open_in_memory().unwrap();
+        assert!(get_shelves(&db).unwrap().is_empty());
+        db.execute_batch(
+            "CREATE TABLE ShelfContent (ShelfName TEXT, ContentId TEXT, _IsDeleted TEXT);
+             INSERT INTO ShelfContent VALUES ('Sci-Fi', 'vol1', 'false');
+             INSERT INTO ShelfContent VALUES ('Abandoned', 'vol1', 'true');
+             INSERT INTO ShelfContent VALUES ('Classics', 'vol1', NULL);",
+        )
+        .unwrap();
+        let shelves = get_shelves(&db).unwrap();
+        assert_eq!(shelves["vol1"], vec!["Classics", "Sci-Fi"]);
+
+        let old = Connection::open_in_memory().unwrap();
+        old.execute_batch(
+            "CREATE TABLE ShelfContent (ShelfName TEXT, ContentId TEXT);
+             INSERT INTO ShelfContent VALUES ('Sci-Fi', 'vol1');",
+        )
+        .unwrap();
+        assert_eq!(get_shelves(&old).unwrap()["vol1"], vec!["Sci-Fi"]);
+        old.execute_batch("DROP TABLE ShelfContent; CREATE TABLE ShelfContent (Name TEXT);")
+            .unwrap();
+        assert!(get_shelves(&old).unwrap().is_empty());
+    }
 }
@@ -1,7 +1,26 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
 use rusqlite::{Connection, Result};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
 
-#[derive(Debug, Serialize)]
+use crate::schema::DbSchema;
+use crate::Book;
+
+const KOBO_DATE_FORMATS: [&str; 2] = ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"];
+
+pub fn parse_kobo_date(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    if let Ok(ts) = DateTime::<Utc>::from_str(value) {
+        return Some(ts);
+    }
+    KOBO_DATE_FORMATS.iter().find_map(|format| {
+        NaiveDateTime::parse_from_str(value, format)
+            .ok()
+            .map(|naive| naive.and_utc())
+    })
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Bookmark {
     pub content_id: String,
     pub content: String,
@@ -11,17 +30,108 @@ pub struct Bookmark {
     pub chapter_progress: f64,
     pub create_date: String,
     pub write_date: String,
+    #[serde(default)]
+    pub annotation: Option<String>,
+    #[serde(default)]
+    pub chapter_index: Option<u32>,
+}
+
+impl Bookmark {
+    pub fn color_name(&self) -> &'static str {
+        match self.color {
+            0 => "yellow",
+            1 => "pink",
+            2 => "blue",
+            3 => "green",
+            _ => "unknown",
+        }
+    }
+
+    pub fn find_book<'a>(&self, books: &'a [Book]) -> Option<&'a Book> {
+        books
+            .iter()
+            .find(|book| book.book_id == self.book_id)
+            .or_else(|| {
+                books
+                    .iter()
+                    .find(|book| !book.title.is_empty() && book.title == self.book_title)
+            })
+    }
+
+    pub fn position(&self) -> f64 {
+        self.chapter_index.unwrap_or(0) as f64 + self.chapter_progress.clamp(0.0, 1.0)
+    }
+
+    pub fn created_at(&self) -> Option<DateTime<Utc>> {
+        parse_kobo_date(&self.create_date)
+    }
+
+    pub fn modified_at(&self) -> Option<DateTime<Utc>> {
+        parse_kobo_date(&self.write_date)
+    }
 }
 
 pub fn get_bookmarks(db: &Connection) -> Result<Vec<Bookmark>> {
-    let mut stmt = db.prepare(
+    get_bookmarks_with(db, &DbSchema::detect(db)?)
+}
+
+pub fn get_bookmarks_with(db: &Connection, schema: &DbSchema) -> Result<Vec<Bookmark>> {
+    if !schema.has_table("Bookmark") {
+        return Ok(Vec::new());
+    }
+    schema.require("Bookmark", &["BookmarkID", "Text", "VolumeID"])?;
+    let has_title =
+        schema.has_column("content", "ContentID") && schema.has_column("content", "Title");
+    let title = if has_title {
+        "COALESCE(c.Title, '')"
+    } else {
+        "''"
+    };
+    let join = if has_title {
+        "LEFT JOIN content c ON c.ContentID = bm.VolumeID"
+    } else {
+        ""
+    };
+    let has_chapters = schema.has_column("Bookmark", "ContentID")
+        && schema.has_column("content", "ContentID")
+        && schema.has_column("content", "BookID")
+        && schema.has_column("content", "VolumeIndex");
+    let chapter_index = if has_chapters {
+        "(SELECT MIN(ch.VolumeIndex) FROM content ch
+             WHERE ch.BookID = bm.VolumeID AND (ch.ContentID = bm.ContentID OR substr(ch.ContentID, 1, length(bm.ContentID) + 1) = bm.ContentID || '-'))"
+    } else {
+        "NULL"
+    };
+    let created = schema.column_or("Bookmark", "DateCreated", "bm.DateCreated", "''");
+    let modified = if schema.has_column("Bookmark", "DateModified") {
+        "bm.DateModified AS DateModified"
+    } else if schema.has_column("Bookmark", "DateCreated") {
+        "bm.DateCreated AS DateModified"
+    } else {
+        "'' AS DateModified"
+    };
+    let mut stmt = db.prepare(&format!(
         "
-        SELECT bm.BookmarkID, bm.Text, bm.VolumeID, bm.Color, bm.ChapterProgress, bm.DateCreated, bm.DateModified, c.Title
+        SELECT bm.BookmarkID, bm.Text, bm.VolumeID, {}, {}, {}, {}, {}, {} AS Title,
+            {} AS ChapterIndex
         FROM Bookmark bm
-        LEFT JOIN content c ON c.ContentID = bm.VolumeID
+        {}
         WHERE bm.Text IS NOT NULL AND bm.Text != ''
         ",
-    )?;
+        schema.column_or("Bookmark", "Color", "bm.Color", "0"),
+        schema.column_or("Bookmark", "ChapterProgress", "bm.ChapterProgress", "0.0"),
+        created,
+        modified,
+        schema.column_or(
+            "Bookmark",
+            "Annotation",
+            "NULLIF(bm.Annotation, '')",
+            "NULL"
+        ),
+        title,
+        chapter_index,
+        join,
+    ))?;
     let bookmarks: Result<Vec<Bookmark>> = stmt
         .query_map([], |row| {
             Ok(Bookmark {
@@ -33,8 +143,76 @@ pub fn get_bookmarks(db: &Connection) -> Result<Vec<Bookmark>> {
                 chapter_progress: row.get("ChapterProgress")?,
                 create_date: row.get("DateCreated")?,
                 write_date: row.get("DateModified")?,
+                annotation: row.get("Annotation")?,
+                chapter_index: row.get("ChapterIndex")?,
             })
         })?
         .collect();
     bookmarks
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_kobo_date() {
+        let expected = DateTime::<Utc>::from_str("2023-01-01T10:06:00Z").unwrap();
+        assert_eq!(parse_kobo_date("2023-01-01T10:06:00Z"), Some(expected));
+        assert_eq!(parse_kobo_date("2023-01-01T10:06:00.000"), Some(expected));
+        assert_eq!(parse_kobo_date("2023-01-01 10:06:00"), Some(expected));
+        assert_eq!(parse_kobo_date("not a date"), None);
+    }
+
+    #[test]
+    fn test_get_bookmarks_chapter_and_note() {
+        let db = Connection::open_in_memory().unwrap();
+        db.execute_batch(
+            "CREATE TABLE content (ContentID TEXT, ContentType INTEGER, Title TEXT, BookID TEXT, VolumeIndex INTEGER);
+             CREATE TABLE Bookmark (BookmarkID TEXT, ContentID TEXT, Text TEXT, VolumeID TEXT, Color INTEGER,
+                 ChapterProgress REAL, DateCreated TEXT, DateModified TEXT, Annotation TEXT);
+             INSERT INTO content VALUES ('vol1', 6, 'Dune', NULL, NULL);
+             INSERT INTO content VALUES ('vol1!!ch3.xhtml-1', 9, 'Chapter 3', 'vol1', 3);
+             INSERT INTO content VALUES ('vol1!!ch3.xhtml-2', 9, 'Chapter 3b', 'vol1', 4);
+             INSERT INTO Bookmark VALUES ('bm1', 'vol1!!ch3.xhtml', 'text', 'vol1', 1, 0.5, '2023-01-01T10:00:00', '2023-01-01T10:00:00', 'note');
+             INSERT INTO content VALUES ('vol1!!chx4.xhtml-1', 9, 'Chapter 4', 'vol1', 7);
+             INSERT INTO Bookmark VALUES ('bm2', 'vol1!!missing.xhtml', 'text', 'vol1', 0, 0.1, '2023-01-01T10:00:00', '2023-01-01T10:00:00', '');
+             INSERT INTO Bookmark VALUES ('bm3', 'vol1!!ch_4.xhtml', 'text', 'vol1', 0, 0.2, '2023-01-01T10:00:00', '2023-01-01T10:00:00', NULL);",
+        )
+        .unwrap();
+        let bookmarks = get_bookmarks(&db).unwrap();
+        assert_eq!(bookmarks[0].book_title, "Dune");
+        assert_eq!(bookmarks[0].chapter_index, Some(3));
+        assert_eq!(bookmarks[0].annotation.as_deref(), Some("note"));
+        assert_eq!(bookmarks[0].position(), 3.5);
+        assert_eq!(bookmarks[1].chapter_index, None);
+        assert_eq!(bookmarks[1].annotation, None);
+        assert_eq!(bookmarks[2].content_id, "bm3");
+        assert_eq!(bookmarks[2].chapter_index, None);
+    }
+
+    #[test]
+    fn test_get_bookmarks_old_firmware() {
+        let db = Connection::open_in_memory().unwrap();
+        assert!(get_bookmarks(&db).unwrap().is_empty());
+        db.execute_batch(
+            "CREATE TABLE content (ContentID TEXT, Title TEXT);
+             CREATE TABLE Bookmark (BookmarkID TEXT, VolumeID TEXT, Text TEXT, DateCreated TEXT);
+             INSERT INTO content VALUES ('vol1', 'Dune');
+             INSERT INTO Bookmark VALUES ('bm1', 'vol1', 'text', '2023-01-01T10:00:00');",
+        )
+        .unwrap();
+        let bookmarks = get_bookmarks(&db).unwrap();
+        assert_eq!(bookmarks[0].book_title, "Dune");
+        assert_eq!(bookmarks[0].color, 0);
+        assert_eq!(bookmarks[0].chapter_progress, 0.0);
+        assert_eq!(bookmarks[0].write_date, "2023-01-01T10:00:00");
+        assert_eq!(bookmarks[0].annotation, None);
+        assert_eq!(bookmarks[0].chapter_index, None);
+
+        db.execute_batch("DROP TABLE Bookmark; CREATE TABLE Bookmark (BookmarkID TEXT);")
+            .unwrap();
+        let err = get_bookmarks(&db).unwrap_err().to_string();
+        assert!(err.contains("Bookmark.Text, Bookmark.VolumeID not found"));
+    }
+}
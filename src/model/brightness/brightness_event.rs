@@ -1,7 +1,8 @@
 use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 
 use super::brightness_value::Brightness;
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BrightnessEvent {
     pub brightness: Brightness,
     pub timestamp: DateTime<Utc>,
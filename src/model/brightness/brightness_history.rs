@@ -1,6 +1,7 @@
 use crate::model::brightness::brightness_event::BrightnessEvent;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct BrightnessHistory {
     pub events: Vec<BrightnessEvent>,
 }
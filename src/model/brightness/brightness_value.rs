@@ -1,4 +1,23 @@
-#[derive(Debug, Clone)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum AdjustmentMethod {
+    Manual,
+    Automatic,
+    Other,
+}
+
+impl AdjustmentMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AdjustmentMethod::Manual => "manual",
+            AdjustmentMethod::Automatic => "automatic",
+            AdjustmentMethod::Other => "other",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Brightness {
     pub method: String,
     pub percentage: u8,
@@ -8,4 +27,16 @@ impl Brightness {
     pub fn new(method: String, percentage: u8) -> Self {
         Self { method, percentage }
     }
+
+    pub fn adjustment_method(&self) -> AdjustmentMethod {
+        let method = self.method.to_ascii_lowercase();
+        if method.contains("auto") {
+            AdjustmentMethod::Automatic
+        } else if method.contains("manual") || method.contains("slider") || method.contains("swipe")
+        {
+            AdjustmentMethod::Manual
+        } else {
+            AdjustmentMethod::Other
+        }
+    }
 }
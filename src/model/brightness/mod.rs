@@ -5,5 +5,5 @@ pub mod natural_light_history;
 
 pub use brightness_event::BrightnessEvent;
 pub use brightness_history::BrightnessHistory;
-pub use brightness_value::Brightness;
+pub use brightness_value::{AdjustmentMethod, Brightness};
 pub use natural_light_history::NaturalLightHistory;
@@ -1,15 +1,17 @@
 use chrono::{DateTime, NaiveDate, Utc};
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::{BTreeMap, HashMap};
 
-use crate::{AppEvent, BrightnessEvent, DictionaryWord, ReadingSession};
+use crate::{AppEvent, Bookmark, BrightnessEvent, DictionaryWord, ReadingSession};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CorrelatedSession {
     pub session: ReadingSession,
     pub dictionary: Vec<DictionaryWord>,
     pub brightness: Vec<BrightnessEvent>,
     pub natural_light: Vec<BrightnessEvent>,
     pub app_events: Vec<AppEvent>,
+    pub annotations: Vec<Bookmark>,
 }
 
 impl CorrelatedSession {
@@ -20,36 +22,61 @@ impl CorrelatedSession {
             brightness: Vec::new(),
             natural_light: Vec::new(),
             app_events: Vec::new(),
+            annotations: Vec::new(),
         }
     }
 }
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct OrphanEvents {
     pub dictionary: Vec<DictionaryWord>,
     pub brightness: Vec<BrightnessEvent>,
     pub natural_light: Vec<BrightnessEvent>,
     pub app_events: Vec<AppEvent>,
+    pub annotations: Vec<Bookmark>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CorrelatedAnalysis {
     pub sessions: Vec<CorrelatedSession>,
     pub orphans: OrphanEvents,
     pub cycles: Vec<ChargeCycle>,
+    #[serde(serialize_with = "serialize_sorted")]
     pub app_start_counts_by_day: HashMap<NaiveDate, usize>,
 }
 
-#[derive(Debug, Clone)]
+fn serialize_sorted<S: Serializer>(
+    counts: &HashMap<NaiveDate, usize>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    counts
+        .iter()
+        .collect::<BTreeMap<_, _>>()
+        .serialize(serializer)
+}
+
+impl CorrelatedAnalysis {
+    pub fn cycle_sessions<'a>(
+        &'a self,
+        cycle: &'a ChargeCycle,
+    ) -> impl Iterator<Item = &'a CorrelatedSession> + 'a {
+        cycle
+            .session_indices
+            .iter()
+            .filter_map(|index| self.sessions.get(*index))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChargeCycle {
     pub start: DateTime<Utc>,
     pub end: Option<DateTime<Utc>>,
-    pub sessions: Vec<CorrelatedSession>,
+    pub session_indices: Vec<usize>,
     pub app_events: Vec<AppEvent>,
     pub metrics: ChargeCycleMetrics,
 }
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct ChargeCycleMetrics {
     pub total_seconds_read: u64,
     pub total_pages: u64,
@@ -57,4 +84,12 @@ pub struct ChargeCycleMetrics {
     pub dictionary_lookups: usize,
     pub brightness_events: usize,
     pub app_starts: usize,
+    pub annotations: usize,
+    pub start_battery: Option<u8>,
+    pub end_battery: Option<u8>,
+    pub charger: Option<String>,
+    pub battery_drain: Option<u8>,
+    pub drain_per_reading_hour: Option<f64>,
+    pub average_brightness: Option<f64>,
+    pub projected_reading_hours: Option<f64>,
 }
@@ -0,0 +1,318 @@
+use chrono::{DateTime, Duration, Utc};
+use std::collections::HashMap;
+
+use uuid::Uuid;
+
+use crate::{session_id, ReadingSession, SessionCompleteness, SESSION_NAMESPACE};
+
+#[derive(Debug, Default)]
+pub struct LeaveMetrics {
+    pub button_press_count: u64,
+    pub seconds_read: u64,
+    pub pages_turned: u64,
+}
+
+#[derive(Debug)]
+pub struct SessionAssembler {
+    namespace: Uuid,
+    open: HashMap<String, ReadingSession>,
+    last_opened: Option<String>,
+}
+
+impl Default for SessionAssembler {
+    fn default() -> Self {
+        Self::with_namespace(SESSION_NAMESPACE)
+    }
+}
+
+fn session_key(volume_id: Option<&str>, book_title: Option<&str>, event_id: &str) -> String {
+    match volume_id.or(book_title) {
+        Some(key) => format!("volume:{}", key),
+        None => format!("event:{}", event_id),
+    }
+}
+
+impl SessionAssembler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_namespace(namespace: Uuid) -> Self {
+        Self {
+            namespace,
+            open: HashMap::new(),
+            last_opened: None,
+        }
+    }
+
+    pub fn open(
+        &mut self,
+        ts: DateTime<Utc>,
+        progress: u8,
+        book_title: Option<String>,
+        volume_id: Option<String>,
+        open_content_id: String,
+    ) -> Option<ReadingSession> {
+        let key = session_key(
+            volume_id.as_deref(),
+            book_title.as_deref(),
+            &open_content_id,
+        );
+        let id = session_id(&self.namespace, &open_content_id);
+        let session =
+            ReadingSession::with_id(id, ts, progress, book_title, volume_id, open_content_id);
+        self.last_opened = Some(key.clone());
+        self.open.insert(key, session)
+    }
+
+    pub fn leave(
+        &mut self,
+        ts: DateTime<Utc>,
+        progress: u8,
+        book_title: Option<String>,
+        volume_id: Option<String>,
+        leave_content_id: String,
+        metrics: LeaveMetrics,
+    ) -> ReadingSession {
+        let key = session_key(
+            volume_id.as_deref(),
+            book_title.as_deref(),
+            &leave_content_id,
+        );
+        let (mut session, completeness) = match self.open.remove(&key) {
+            Some(session) => (session, SessionCompleteness::Complete),
+            None => {
+                let start = ts - Duration::seconds(metrics.seconds_read as i64);
+                let session = ReadingSession::with_id(
+                    session_id(&self.namespace, &leave_content_id),
+                    start,
+                    progress,
+                    book_title,
+                    volume_id,
+                    String::new(),
+                );
+                (session, SessionCompleteness::MissingOpen)
+            }
+        };
+        if self.last_opened.as_deref() == Some(key.as_str()) {
+            self.last_opened = None;
+        }
+
+        match session.complete_session(
+            ts,
+            progress,
+            metrics.button_press_count,
+            metrics.seconds_read,
+            metrics.pages_turned,
+            leave_content_id.clone(),
+        ) {
+            Ok(session) => session.completeness = completeness,
+            Err(_) => {
+                session.reject_session(
+                    ts,
+                    progress,
+                    metrics.button_press_count,
+                    metrics.seconds_read,
+                    metrics.pages_turned,
+                    leave_content_id,
+                );
+            }
+        }
+        session
+    }
+
+    pub fn current(&self) -> Option<&ReadingSession> {
+        self.last_opened.as_ref().and_then(|key| self.open.get(key))
+    }
+
+    pub fn finish(self) -> Vec<ReadingSession> {
+        let mut remaining: Vec<ReadingSession> = self.open.into_values().collect();
+        remaining.sort_by_key(|session| session.time_start);
+        remaining
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn ts(value: &str) -> DateTime<Utc> {
+        DateTime::<Utc>::from_str(value).unwrap()
+    }
+
+    fn metrics(seconds_read: u64) -> LeaveMetrics {
+        LeaveMetrics {
+            button_press_count: 1,
+            seconds_read,
+            pages_turned: 2,
+        }
+    }
+
+    fn volume(id: &str) -> Option<String> {
+        Some(id.to_string())
+    }
+
+    #[test]
+    fn test_reopen_emits_missing_close() {
+        let mut assembler = SessionAssembler::new();
+        let first = ts("2023-01-01T10:00:00Z");
+        assert!(assembler
+            .open(first, 5, None, volume("book1"), "open1".to_string())
+            .is_none());
+        let displaced = assembler
+            .open(
+                ts("2023-01-01T12:00:00Z"),
+                9,
+                None,
+                volume("book1"),
+                "open2".to_string(),
+            )
+            .unwrap();
+        assert_eq!(displaced.open_content_id, "open1");
+        assert_eq!(displaced.completeness, SessionCompleteness::MissingClose);
+        assert!(displaced.time_end.is_none());
+        assert_eq!(assembler.current().unwrap().open_content_id, "open2");
+    }
+
+    #[test]
+    fn test_leave_pairs_by_volume() {
+        let mut assembler = SessionAssembler::new();
+        assembler.open(
+            ts("2023-01-01T10:00:00Z"),
+            5,
+            None,
+            volume("book1"),
+            "open1".to_string(),
+        );
+        let orphan = assembler.leave(
+            ts("2023-01-01T10:10:00Z"),
+            40,
+            None,
+            volume("book2"),
+            "leave2".to_string(),
+            metrics(300),
+        );
+        assert_eq!(orphan.completeness, SessionCompleteness::MissingOpen);
+        assert_eq!(orphan.time_start, ts("2023-01-01T10:05:00Z"));
+        assert!(!orphan.is_complete());
+
+        let session = assembler.leave(
+            ts("2023-01-01T10:20:00Z"),
+            10,
+            None,
+            volume("book1"),
+            "leave1".to_string(),
+            metrics(1200),
+        );
+        assert_eq!(session.completeness, SessionCompleteness::Complete);
+        assert_eq!(session.open_content_id, "open1");
+        assert!(assembler.current().is_none());
+        assert!(assembler.finish().is_empty());
+    }
+
+    #[test]
+    fn test_session_ids_are_deterministic() {
+        let open = |mut assembler: SessionAssembler| {
+            assembler.open(
+                ts("2023-01-01T10:00:00Z"),
+                5,
+                None,
+                volume("book1"),
+                "open1".to_string(),
+            );
+            assembler.finish().remove(0).id
+        };
+        let first = open(SessionAssembler::new());
+        assert_eq!(first, open(SessionAssembler::new()));
+        assert_eq!(first, session_id(&SESSION_NAMESPACE, "open1"));
+
+        let account = crate::account_namespace("user-a");
+        let on_account = open(SessionAssembler::with_namespace(account));
+        assert_ne!(first, on_account);
+        assert_eq!(on_account, session_id(&account, "open1"));
+
+        let mut assembler = SessionAssembler::with_namespace(account);
+        let orphan = assembler.leave(
+            ts("2023-01-01T10:10:00Z"),
+            40,
+            None,
+            volume("book2"),
+            "leave2".to_string(),
+            metrics(300),
+        );
+        assert_eq!(orphan.id, session_id(&account, "leave2"));
+    }
+
+    #[test]
+    fn test_backward_leave_is_rejected() {
+        let mut assembler = SessionAssembler::new();
+        assembler.open(
+            ts("2023-01-01T10:00:00Z"),
+            50,
+            None,
+            volume("book1"),
+            "open1".to_string(),
+        );
+        let session = assembler.leave(
+            ts("2023-01-01T10:10:00Z"),
+            20,
+            None,
+            volume("book1"),
+            "leave1".to_string(),
+            metrics(600),
+        );
+        assert_eq!(session.completeness, SessionCompleteness::Rejected);
+        assert_eq!(session.open_content_id, "open1");
+        assert_eq!(session.leave_content_id.as_deref(), Some("leave1"));
+        assert_eq!(session.end_progress, Some(20));
+        assert!(!session.is_complete());
+        assert!(assembler.finish().is_empty());
+    }
+
+    #[test]
+    fn test_unkeyed_events_do_not_pair() {
+        let mut assembler = SessionAssembler::new();
+        assembler.open(
+            ts("2023-01-01T10:00:00Z"),
+            5,
+            None,
+            None,
+            "open1".to_string(),
+        );
+        assert!(assembler
+            .open(
+                ts("2023-01-01T10:05:00Z"),
+                5,
+                None,
+                None,
+                "open2".to_string()
+            )
+            .is_none());
+        let orphan = assembler.leave(
+            ts("2023-01-01T10:10:00Z"),
+            10,
+            None,
+            None,
+            "leave1".to_string(),
+            metrics(300),
+        );
+        assert_eq!(orphan.completeness, SessionCompleteness::MissingOpen);
+        assert_eq!(assembler.finish().len(), 2);
+    }
+
+    #[test]
+    fn test_finish_flushes_open_sessions() {
+        let mut assembler = SessionAssembler::new();
+        assembler.open(
+            ts("2023-01-01T10:00:00Z"),
+            5,
+            None,
+            volume("book1"),
+            "open1".to_string(),
+        );
+        let remaining = assembler.finish();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].completeness, SessionCompleteness::MissingClose);
+    }
+}
@@ -1,5 +1,9 @@
+pub mod assembler;
 pub mod reading_session;
 pub mod sessions;
+pub mod stitched;
 
+pub use assembler::*;
 pub use reading_session::*;
 pub use sessions::*;
+pub use stitched::*;
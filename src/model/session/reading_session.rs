@@ -1,6 +1,26 @@
 use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::MIN_VALID_SESSION_TIME;
+
+pub const SESSION_NAMESPACE: Uuid = Uuid::from_u128(0x3b1d_6c2e_8f4a_5e91_a7c3_2d0f_9b64_e815);
+
+pub fn device_namespace(device_id: &str) -> Uuid {
+    Uuid::new_v5(
+        &SESSION_NAMESPACE,
+        format!("device:{}", device_id).as_bytes(),
+    )
+}
+
+pub fn account_namespace(user_id: &str) -> Uuid {
+    Uuid::new_v5(&SESSION_NAMESPACE, user_id.as_bytes())
+}
+
+pub fn session_id(namespace: &Uuid, event_id: &str) -> Uuid {
+    Uuid::new_v5(namespace, event_id.as_bytes())
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum ReadingMetric {
     SecondsRead,
@@ -15,7 +35,28 @@ pub enum ReadingSessionError {
     InvalidProgressValue,
 }
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionCompleteness {
+    Complete,
+    MissingOpen,
+    #[default]
+    MissingClose,
+    Rejected,
+}
+
+impl SessionCompleteness {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SessionCompleteness::Complete => "complete",
+            SessionCompleteness::MissingOpen => "missing_open",
+            SessionCompleteness::MissingClose => "missing_close",
+            SessionCompleteness::Rejected => "rejected",
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct ReadingSession {
     pub id: Uuid,
     pub open_content_id: String,
@@ -29,6 +70,7 @@ pub struct ReadingSession {
     pub button_press_count: Option<u64>,
     pub seconds_read: Option<u64>,
     pub pages_turned: Option<u64>,
+    pub completeness: SessionCompleteness,
 }
 
 impl ReadingSession {
@@ -38,9 +80,28 @@ impl ReadingSession {
         book_title: Option<String>,
         volume_id: Option<String>,
         open_content_id: String,
+    ) -> Self {
+        let id = session_id(&SESSION_NAMESPACE, &open_content_id);
+        Self::with_id(
+            id,
+            ts,
+            start_progress,
+            book_title,
+            volume_id,
+            open_content_id,
+        )
+    }
+
+    pub fn with_id(
+        id: Uuid,
+        ts: DateTime<Utc>,
+        start_progress: u8,
+        book_title: Option<String>,
+        volume_id: Option<String>,
+        open_content_id: String,
     ) -> Self {
         Self {
-            id: Uuid::new_v4(),
+            id,
             time_start: ts,
             time_end: None,
             open_content_id,
@@ -52,6 +113,7 @@ impl ReadingSession {
             button_press_count: None,
             seconds_read: None,
             pages_turned: None,
+            completeness: SessionCompleteness::MissingClose,
         }
     }
 
@@ -78,11 +140,43 @@ impl ReadingSession {
         self.seconds_read = Some(seconds_read);
         self.pages_turned = Some(pages_turned);
         self.leave_content_id = Some(leave_content_id);
+        self.completeness = SessionCompleteness::Complete;
         Ok(self)
     }
 
+    pub fn reject_session(
+        &mut self,
+        time_end: DateTime<Utc>,
+        end_progress: u8,
+        button_press_count: u64,
+        seconds_read: u64,
+        pages_turned: u64,
+        leave_content_id: String,
+    ) -> &mut Self {
+        self.time_end = Some(time_end);
+        self.end_progress = Some(end_progress);
+        self.button_press_count = Some(button_press_count);
+        self.seconds_read = Some(seconds_read);
+        self.pages_turned = Some(pages_turned);
+        self.leave_content_id = Some(leave_content_id);
+        self.completeness = SessionCompleteness::Rejected;
+        self
+    }
+
     pub fn is_complete(&self) -> bool {
-        self.end_progress.is_some()
+        self.completeness == SessionCompleteness::Complete
+    }
+
+    pub fn is_partial(&self) -> bool {
+        !self.is_complete()
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.is_valid_with_min(MIN_VALID_SESSION_TIME)
+    }
+
+    pub fn is_valid_with_min(&self, min_seconds: u64) -> bool {
+        self.is_complete() && self.seconds_read.unwrap_or(0) >= min_seconds
     }
 
     pub fn duration(&self) -> Option<Duration> {
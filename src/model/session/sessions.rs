@@ -1,8 +1,11 @@
+use serde::{Deserialize, Serialize};
+
 use crate::ReadingSession;
 
-const MIN_VALID_SESSION_TIME: u64 = 60;
+pub const MIN_VALID_SESSION_TIME: u64 = 60;
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
 pub struct ReadingSessions {
     sessions: Vec<ReadingSession>,
 }
@@ -18,9 +21,15 @@ impl ReadingSessions {
         self.valid_sessions()
     }
     pub fn valid_sessions(&self) -> impl Iterator<Item = &ReadingSession> {
+        self.valid_sessions_with_min(MIN_VALID_SESSION_TIME)
+    }
+    pub fn valid_sessions_with_min(
+        &self,
+        min_seconds: u64,
+    ) -> impl Iterator<Item = &ReadingSession> {
         self.sessions
             .iter()
-            .filter(|s| s.is_complete() && s.seconds_read.unwrap_or(0) >= MIN_VALID_SESSION_TIME)
+            .filter(move |s| s.is_valid_with_min(min_seconds))
     }
     pub fn sessions_count(&self) -> usize {
         self.sessions.len()
@@ -0,0 +1,178 @@
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
+
+use crate::{ReadingSession, ReadingSessions};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StitchedSession {
+    pub session: ReadingSession,
+    pub parts: Vec<ReadingSession>,
+}
+
+fn add_optional(total: Option<u64>, value: Option<u64>) -> Option<u64> {
+    match (total, value) {
+        (None, None) => None,
+        (total, value) => Some(total.unwrap_or(0) + value.unwrap_or(0)),
+    }
+}
+
+impl StitchedSession {
+    pub fn new(part: ReadingSession) -> Self {
+        Self {
+            session: part.clone(),
+            parts: vec![part],
+        }
+    }
+
+    pub fn push(&mut self, part: ReadingSession) {
+        let merged = &mut self.session;
+        merged.time_end = part.time_end;
+        merged.end_progress = part.end_progress;
+        merged.leave_content_id = part.leave_content_id.clone();
+        if merged.book_title.is_none() {
+            merged.book_title = part.book_title.clone();
+        }
+        merged.button_press_count =
+            add_optional(merged.button_press_count, part.button_press_count);
+        merged.seconds_read = add_optional(merged.seconds_read, part.seconds_read);
+        merged.pages_turned = add_optional(merged.pages_turned, part.pages_turned);
+        self.parts.push(part);
+    }
+
+    pub fn is_stitched(&self) -> bool {
+        self.parts.len() > 1
+    }
+
+    fn accepts(&self, next: &ReadingSession, max_gap: Duration) -> bool {
+        let Some(end) = self.session.time_end else {
+            return false;
+        };
+        self.session.volume_id.is_some()
+            && self.session.volume_id == next.volume_id
+            && next.time_start >= end
+            && next.time_start - end <= max_gap
+    }
+}
+
+impl ReadingSessions {
+    pub fn stitch(&self, max_gap: Duration) -> Vec<StitchedSession> {
+        let mut sessions: Vec<&ReadingSession> = self
+            .get_sessions()
+            .iter()
+            .filter(|session| session.is_complete())
+            .collect();
+        sessions.sort_by_key(|session| session.time_start);
+
+        let mut stitched: Vec<StitchedSession> = Vec::new();
+        for session in sessions {
+            match stitched.last_mut() {
+                Some(current) if current.accepts(session, max_gap) => current.push(session.clone()),
+                _ => stitched.push(StitchedSession::new(session.clone())),
+            }
+        }
+        stitched
+    }
+
+    pub fn stitched(&self, max_gap: Duration) -> ReadingSessions {
+        let mut merged = ReadingSessions::new();
+        for stitched in self.stitch(max_gap) {
+            merged.add_session(stitched.session);
+        }
+        merged
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, Utc};
+    use std::str::FromStr;
+
+    fn add(sessions: &mut ReadingSessions, volume: &str, start: &str, end: &str, seconds: u64) {
+        let start = DateTime::<Utc>::from_str(start).unwrap();
+        let end = DateTime::<Utc>::from_str(end).unwrap();
+        let mut session = ReadingSession::new(
+            start,
+            10,
+            None,
+            Some(volume.to_string()),
+            format!("open-{}", start),
+        );
+        session
+            .complete_session(end, 12, 2, seconds, 3, format!("leave-{}", end))
+            .unwrap();
+        sessions.add_session(session);
+    }
+
+    #[test]
+    fn test_stitch_merges_short_gaps_on_same_volume() {
+        let mut sessions = ReadingSessions::new();
+        add(
+            &mut sessions,
+            "book1",
+            "2023-01-01T21:00:00Z",
+            "2023-01-01T21:00:50Z",
+            50,
+        );
+        add(
+            &mut sessions,
+            "book1",
+            "2023-01-01T21:01:30Z",
+            "2023-01-01T21:02:20Z",
+            50,
+        );
+        add(
+            &mut sessions,
+            "book1",
+            "2023-01-01T21:03:00Z",
+            "2023-01-01T21:03:40Z",
+            40,
+        );
+        add(
+            &mut sessions,
+            "book2",
+            "2023-01-01T21:04:00Z",
+            "2023-01-01T21:10:00Z",
+            360,
+        );
+        add(
+            &mut sessions,
+            "book2",
+            "2023-01-01T23:00:00Z",
+            "2023-01-01T23:10:00Z",
+            600,
+        );
+
+        let stitched = sessions.stitch(Duration::minutes(2));
+        assert_eq!(stitched.len(), 3);
+
+        let first = &stitched[0];
+        assert!(first.is_stitched());
+        assert_eq!(first.parts.len(), 3);
+        assert_eq!(first.session.seconds_read, Some(140));
+        assert_eq!(first.session.pages_turned, Some(9));
+        assert_eq!(first.session.button_press_count, Some(6));
+        assert_eq!(
+            first.session.time_end,
+            Some(DateTime::<Utc>::from_str("2023-01-01T21:03:40Z").unwrap())
+        );
+        assert_eq!(
+            first.session.open_content_id,
+            first.parts[0].open_content_id
+        );
+        assert_eq!(
+            first.session.leave_content_id,
+            first.parts[2].leave_content_id
+        );
+        assert!(!stitched[2].is_stitched());
+
+        assert_eq!(sessions.valid_sessions().count(), 2);
+        assert_eq!(
+            sessions
+                .stitched(Duration::minutes(2))
+                .valid_sessions()
+                .count(),
+            3
+        );
+    }
+}
@@ -1,10 +1,15 @@
+use crate::correlation::{sort_sessions, CorrelationConfig, SessionIndex};
+use crate::statistics::brightness_stats::BrightnessStats;
+use crate::statistics::timeseries::local_date;
 use crate::{
-    get_bookmarks, AppEvent, AppEventKind, Book, Bookmark, Brightness, BrightnessEvent,
-    BrightnessHistory, ChargeCycle, ChargeCycleMetrics, CorrelatedAnalysis, CorrelatedSession,
-    DictionaryWord, NaturalLightHistory, OrphanEvents, ReadingSession, ReadingSessions,
+    account_namespace, device_namespace, get_bookmarks_with, get_shelves_with, AppEvent,
+    AppEventKind, Book, Bookmark, Brightness, BrightnessEvent, BrightnessHistory, ChargeCycle,
+    ChargeCycleMetrics, CorrelatedAnalysis, CorrelatedSession, DbSchema, DictionaryWord,
+    LeaveMetrics, NaturalLightHistory, OrphanEvents, ReadingSession, ReadingSessions,
+    SessionAssembler, SESSION_NAMESPACE,
 };
-use chrono::{DateTime, Duration, Utc};
-use rusqlite::{params_from_iter, Connection, OpenFlags};
+use chrono::{DateTime, TimeZone, Utc};
+use rusqlite::{params_from_iter, Connection, OpenFlags, OptionalExtension};
 use std::collections::{HashMap, HashSet};
 use std::path::Path;
 use std::str::FromStr;
@@ -78,9 +83,10 @@ pub enum ParseOption {
     PluggedIn,
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
 pub struct EventAnalysis {
     pub sessions: Option<ReadingSessions>,
+    #[serde(default, with = "term_counts")]
     pub terms: Option<HashMap<DictionaryWord, usize>>,
     pub brightness_history: Option<BrightnessHistory>,
     pub natural_light_history: Option<NaturalLightHistory>,
@@ -89,10 +95,86 @@ pub struct EventAnalysis {
     pub app_events: Option<Vec<AppEvent>>,
 }
 
+mod term_counts {
+    use crate::DictionaryWord;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::collections::HashMap;
+
+    #[derive(Serialize, Deserialize)]
+    struct TermCount {
+        word: DictionaryWord,
+        count: usize,
+    }
+
+    pub fn serialize<S: Serializer>(
+        terms: &Option<HashMap<DictionaryWord, usize>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let counts = terms.as_ref().map(|terms| {
+            let mut counts: Vec<TermCount> = terms
+                .iter()
+                .map(|(word, count)| TermCount {
+                    word: word.clone(),
+                    count: *count,
+                })
+                .collect();
+            counts.sort_by(|a, b| {
+                (a.word.term(), a.word.lang(), a.word.session_id()).cmp(&(
+                    b.word.term(),
+                    b.word.lang(),
+                    b.word.session_id(),
+                ))
+            });
+            counts
+        });
+        counts.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<HashMap<DictionaryWord, usize>>, D::Error> {
+        let counts = Option::<Vec<TermCount>>::deserialize(deserializer)?;
+        Ok(counts.map(|counts| {
+            counts
+                .into_iter()
+                .map(|entry| (entry.word, entry.count))
+                .collect()
+        }))
+    }
+}
+
 pub struct Parser;
 
 impl Parser {
+    pub fn session_namespace(db: &Connection) -> rusqlite::Result<Uuid> {
+        Self::session_namespace_with(db, &DbSchema::detect(db)?)
+    }
+
+    pub fn session_namespace_with(db: &Connection, schema: &DbSchema) -> rusqlite::Result<Uuid> {
+        let first_user_value = |column: &str| -> rusqlite::Result<Option<String>> {
+            if !schema.has_column("user", column) {
+                return Ok(None);
+            }
+            db.query_row(
+                &format!(
+                    "SELECT {0} FROM user WHERE COALESCE({0}, '') <> '' ORDER BY {0} LIMIT 1",
+                    column
+                ),
+                [],
+                |row| row.get(0),
+            )
+            .optional()
+        };
+        if let Some(device_id) = first_user_value("___DeviceID")? {
+            return Ok(device_namespace(&device_id));
+        }
+        Ok(first_user_value("UserID")?
+            .map(|user_id| account_namespace(&user_id))
+            .unwrap_or(SESSION_NAMESPACE))
+    }
+
     pub fn parse_events(db: &Connection, option: ParseOption) -> rusqlite::Result<EventAnalysis> {
+        let schema = DbSchema::detect(db)?;
         let mut analysis = EventAnalysis::default();
         let event_types = event_types_for_option(option);
         let include_bookmarks = matches!(option, ParseOption::All | ParseOption::Bookmarks);
@@ -109,16 +191,17 @@ impl Parser {
         );
 
         if include_bookmarks {
-            analysis.bookmarks = Some(get_bookmarks(db)?);
+            analysis.bookmarks = Some(get_bookmarks_with(db, &schema)?);
         }
 
         if !event_types.is_empty() {
-            let q = build_event_query(event_types.len());
+            let q = build_event_query(&schema, event_types.len())?;
 
             let mut stmt = db.prepare(&q)?;
             let mut rows = stmt.query(params_from_iter(event_types.iter().copied()))?;
 
-            let mut current_session: Option<ReadingSession> = None;
+            let mut assembler =
+                SessionAssembler::with_namespace(Self::session_namespace_with(db, &schema)?);
             let mut sessions_vec = ReadingSessions::new();
             let mut terms_map = HashMap::new();
             let mut brightness_hist = BrightnessHistory::new();
@@ -152,28 +235,28 @@ impl Parser {
                         }
 
                         let metrics = if event_type == "LeaveContent" {
-                            let metr_json: String = row.get("Metrics")?;
-                            Some(from_json::<LeaveContentMetrics>(&metr_json, 2)?)
+                            let metr_json: Option<String> = row.get("Metrics")?;
+                            metr_json
+                                .map(|json| from_json::<LeaveContentMetrics>(&json, 2))
+                                .transpose()?
                         } else {
                             None
                         };
 
-                        match handle_reading_session_event(
+                        if let Some(session) = handle_reading_session_event(
                             &event_type,
                             &event_id,
-                            &mut current_session,
+                            &mut assembler,
                             ts,
                             progress,
                             &attr,
                             metrics,
                         ) {
-                            Ok(Some(session)) => sessions_vec.add_session(session),
-                            Ok(None) => {}
-                            Err(e) => eprintln!("Errore evento {}: {:?}", &event_id, e),
+                            sessions_vec.add_session(session);
                         }
                     }
                     "DictionaryLookup" if include_dictionary => {
-                        let session_id = current_session.as_ref().map(|s| s.id);
+                        let session_id = assembler.current().map(|s| s.id);
                         let attr_json: String = row.get("Attributes")?;
                         *terms_map
                             .entry(on_dictionary_lookup(&attr_json, session_id)?)
@@ -181,13 +264,17 @@ impl Parser {
                     }
                     "BrightnessAdjusted" if include_brightness => {
                         let attr_json: String = row.get("Attributes")?;
-                        let metr_json: String = row.get("Metrics")?;
+                        let Some(metr_json) = row.get::<_, Option<String>>("Metrics")? else {
+                            continue;
+                        };
                         let event = on_light_adjusted(&attr_json, &metr_json, ts)?;
                         brightness_hist.insert(event);
                     }
                     "NaturalLightAdjusted" if include_natural_light => {
                         let attr_json: String = row.get("Attributes")?;
-                        let metr_json: String = row.get("Metrics")?;
+                        let Some(metr_json) = row.get::<_, Option<String>>("Metrics")? else {
+                            continue;
+                        };
                         let event = on_light_adjusted(&attr_json, &metr_json, ts)?;
                         natural_light_hist.insert(event);
                     }
@@ -207,7 +294,14 @@ impl Parser {
                 }
             }
 
-            let mut books_from_db = get_books_by_volume_id(db, &volume_ids_to_query)?;
+            for session in assembler.finish() {
+                sessions_vec.add_session(session);
+            }
+            sessions_vec
+                .get_mut_sessions()
+                .sort_by_key(|session| session.time_start);
+
+            let mut books_from_db = get_books_by_volume_id(db, &schema, &volume_ids_to_query)?;
             books_from_db.extend(books_from_events);
             analysis.books = Some(books_from_db.values().cloned().collect());
 
@@ -225,10 +319,11 @@ impl Parser {
             if include_dictionary {
                 analysis.terms = Some(terms_map);
             }
-            if include_brightness {
+            let has_metrics = schema.has_column("AnalyticsEvents", "Metrics");
+            if include_brightness && has_metrics {
                 analysis.brightness_history = Some(brightness_hist);
             }
-            if include_natural_light {
+            if include_natural_light && has_metrics {
                 analysis.natural_light_history = Some(natural_light_hist);
             }
             if include_app_events {
@@ -239,15 +334,31 @@ impl Parser {
     }
 
     pub fn parse_correlated(db: &Connection) -> rusqlite::Result<CorrelatedAnalysis> {
-        const TOLERANCE_SECONDS: i64 = 30;
-        let tolerance = Duration::seconds(TOLERANCE_SECONDS);
+        Self::parse_correlated_in(db, &Utc)
+    }
+
+    pub fn parse_correlated_in<Tz: TimeZone>(
+        db: &Connection,
+        tz: &Tz,
+    ) -> rusqlite::Result<CorrelatedAnalysis> {
+        Self::parse_correlated_with(db, &CorrelationConfig::default(), tz)
+    }
+
+    pub fn parse_correlated_with<Tz: TimeZone>(
+        db: &Connection,
+        config: &CorrelationConfig,
+        tz: &Tz,
+    ) -> rusqlite::Result<CorrelatedAnalysis> {
+        let schema = DbSchema::detect(db)?;
+        let strategy = config.strategy;
         let event_types = event_types_for_option(ParseOption::All);
-        let q = build_event_query(event_types.len());
+        let q = build_event_query(&schema, event_types.len())?;
 
         let mut stmt = db.prepare(&q)?;
         let mut rows = stmt.query(params_from_iter(event_types.iter().copied()))?;
 
-        let mut current_session: Option<ReadingSession> = None;
+        let mut assembler =
+            SessionAssembler::with_namespace(Self::session_namespace_with(db, &schema)?);
         let mut sessions_vec = ReadingSessions::new();
         let mut dictionary_events = Vec::new();
         let mut brightness_events = Vec::new();
@@ -281,24 +392,24 @@ impl Parser {
                     }
 
                     let metrics = if event_type == "LeaveContent" {
-                        let metr_json: String = row.get("Metrics")?;
-                        Some(from_json::<LeaveContentMetrics>(&metr_json, 2)?)
+                        let metr_json: Option<String> = row.get("Metrics")?;
+                        metr_json
+                            .map(|json| from_json::<LeaveContentMetrics>(&json, 2))
+                            .transpose()?
                     } else {
                         None
                     };
 
-                    match handle_reading_session_event(
+                    if let Some(session) = handle_reading_session_event(
                         &event_type,
                         &event_id,
-                        &mut current_session,
+                        &mut assembler,
                         ts,
                         progress,
                         &attr,
                         metrics,
                     ) {
-                        Ok(Some(session)) => sessions_vec.add_session(session),
-                        Ok(None) => {}
-                        Err(e) => eprintln!("Errore evento {}: {:?}", &event_id, e),
+                        sessions_vec.add_session(session);
                     }
                 }
                 "DictionaryLookup" => {
@@ -311,13 +422,17 @@ impl Parser {
                 }
                 "BrightnessAdjusted" => {
                     let attr_json: String = row.get("Attributes")?;
-                    let metr_json: String = row.get("Metrics")?;
+                    let Some(metr_json) = row.get::<_, Option<String>>("Metrics")? else {
+                        continue;
+                    };
                     let event = on_light_adjusted(&attr_json, &metr_json, ts)?;
                     brightness_events.push(event);
                 }
                 "NaturalLightAdjusted" => {
                     let attr_json: String = row.get("Attributes")?;
-                    let metr_json: String = row.get("Metrics")?;
+                    let Some(metr_json) = row.get::<_, Option<String>>("Metrics")? else {
+                        continue;
+                    };
                     let event = on_light_adjusted(&attr_json, &metr_json, ts)?;
                     natural_light_events.push(event);
                 }
@@ -335,7 +450,11 @@ impl Parser {
             }
         }
 
-        let mut books_from_db = get_books_by_volume_id(db, &volume_ids_to_query)?;
+        for session in assembler.finish() {
+            sessions_vec.add_session(session);
+        }
+
+        let mut books_from_db = get_books_by_volume_id(db, &schema, &volume_ids_to_query)?;
         books_from_db.extend(books_from_events);
 
         for session in sessions_vec.get_mut_sessions() {
@@ -349,11 +468,13 @@ impl Parser {
         let sessions = std::mem::take(sessions_vec.get_mut_sessions());
         let mut correlated_sessions: Vec<CorrelatedSession> =
             sessions.into_iter().map(CorrelatedSession::new).collect();
+        sort_sessions(&mut correlated_sessions);
+        let session_index = SessionIndex::new(&correlated_sessions);
 
         let mut orphan_dictionary = Vec::new();
         for timed in dictionary_events {
             if let Some(index) =
-                find_session_index(&correlated_sessions, timed.timestamp, tolerance)
+                session_index.find_with(timed.timestamp, config.dictionary_tolerance, strategy)
             {
                 let session_id = correlated_sessions[index].session.id;
                 let word = DictionaryWord::new(
@@ -374,7 +495,7 @@ impl Parser {
         let mut orphan_brightness = Vec::new();
         for event in brightness_events {
             if let Some(index) =
-                find_session_index(&correlated_sessions, event.timestamp, tolerance)
+                session_index.find_with(event.timestamp, config.brightness_tolerance, strategy)
             {
                 correlated_sessions[index].brightness.push(event);
             } else {
@@ -385,7 +506,7 @@ impl Parser {
         let mut orphan_natural_light = Vec::new();
         for event in natural_light_events {
             if let Some(index) =
-                find_session_index(&correlated_sessions, event.timestamp, tolerance)
+                session_index.find_with(event.timestamp, config.natural_light_tolerance, strategy)
             {
                 correlated_sessions[index].natural_light.push(event);
             } else {
@@ -395,12 +516,38 @@ impl Parser {
 
         let mut orphan_app_events = Vec::new();
         for event in app_events {
-            if let Some(index) =
-                find_session_index(&correlated_sessions, event.timestamp, tolerance)
-            {
-                correlated_sessions[index].app_events.push(event);
+            let index = if config.attaches_app_event(event.kind) {
+                session_index.find_with(event.timestamp, config.app_event_tolerance, strategy)
             } else {
-                orphan_app_events.push(event);
+                None
+            };
+            match index {
+                Some(index) => correlated_sessions[index].app_events.push(event),
+                None => orphan_app_events.push(event),
+            }
+        }
+
+        let mut orphan_annotations = Vec::new();
+        if config.attach_bookmarks {
+            for bookmark in get_bookmarks_with(db, &schema)? {
+                let index = bookmark.created_at().and_then(|created| {
+                    session_index.find_where(
+                        created,
+                        config.bookmark_tolerance,
+                        strategy,
+                        |index| {
+                            correlated_sessions[index]
+                                .session
+                                .volume_id
+                                .as_deref()
+                                .is_none_or(|volume_id| volume_id == bookmark.book_id)
+                        },
+                    )
+                });
+                match index {
+                    Some(index) => correlated_sessions[index].annotations.push(bookmark),
+                    None => orphan_annotations.push(bookmark),
+                }
             }
         }
 
@@ -409,6 +556,7 @@ impl Parser {
             brightness: orphan_brightness,
             natural_light: orphan_natural_light,
             app_events: orphan_app_events,
+            annotations: orphan_annotations,
         };
 
         let mut all_app_events: Vec<AppEvent> = correlated_sessions
@@ -416,14 +564,27 @@ impl Parser {
             .flat_map(|session| session.app_events.iter().cloned())
             .collect();
         all_app_events.extend(orphans.app_events.iter().cloned());
+        all_app_events.sort_by_key(|event| event.timestamp);
 
-        let cycles = build_charge_cycles(&correlated_sessions, &all_app_events);
+        let mut all_brightness: Vec<BrightnessEvent> = correlated_sessions
+            .iter()
+            .flat_map(|session| session.brightness.iter().cloned())
+            .collect();
+        all_brightness.extend(orphans.brightness.iter().cloned());
+        all_brightness.sort_by_key(|event| event.timestamp);
+
+        let cycles = build_charge_cycles(
+            &correlated_sessions,
+            &session_index,
+            &all_app_events,
+            &all_brightness,
+        );
         let mut app_start_counts_by_day = HashMap::new();
         for event in all_app_events
             .iter()
             .filter(|event| event.kind == AppEventKind::AppStart)
         {
-            let day = event.timestamp.date_naive();
+            let day = local_date(event.timestamp, tz);
             *app_start_counts_by_day.entry(day).or_insert(0) += 1;
         }
 
@@ -438,10 +599,10 @@ impl Parser {
         path: P,
         option: ParseOption,
     ) -> rusqlite::Result<EventAnalysis> {
-        let path_ref = path.as_ref();
-        let conn = Connection::open(path_ref).or_else(|err| {
-            Connection::open_with_flags(path_ref, OpenFlags::SQLITE_OPEN_READ_ONLY).map_err(|_| err)
-        })?;
+        let conn = Connection::open_with_flags(
+            path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
         Self::parse_events(&conn, option)
     }
 }
@@ -449,45 +610,47 @@ impl Parser {
 fn handle_reading_session_event(
     event_type: &str,
     event_id: &str,
-    current_session: &mut Option<ReadingSession>,
+    assembler: &mut SessionAssembler,
     ts: DateTime<Utc>,
     progress: u8,
     attr: &ReadingSessionAttributes,
     metrics: Option<LeaveContentMetrics>,
-) -> Result<Option<ReadingSession>, ParseError> {
+) -> Option<ReadingSession> {
     match event_type {
-        "OpenContent" => {
-            *current_session = Some(ReadingSession::new(
+        "OpenContent" => assembler.open(
+            ts,
+            progress,
+            attr.title.clone(),
+            attr.volumeid.clone(),
+            event_id.to_string(),
+        ),
+        "LeaveContent" => {
+            let leave_metrics = metrics
+                .as_ref()
+                .map(|m| LeaveMetrics {
+                    button_press_count: m.button_press_count as u64,
+                    seconds_read: m.seconds_read as u64,
+                    pages_turned: m.pages_turned as u64,
+                })
+                .unwrap_or_default();
+            let mut session = assembler.leave(
                 ts,
                 progress,
                 attr.title.clone(),
                 attr.volumeid.clone(),
                 event_id.to_string(),
-            ));
-            Ok(None)
-        }
-        "LeaveContent" => {
-            let mut session = current_session
-                .take()
-                .ok_or(ParseError::SessionCompletionFailed)?;
-            let m = metrics.ok_or(ParseError::SessionCompletionFailed)?;
-            if session
-                .complete_session(
-                    ts,
-                    progress,
-                    m.button_press_count as u64,
-                    m.seconds_read as u64,
-                    m.pages_turned as u64,
-                    event_id.to_string(),
-                )
-                .is_err()
-            {
-                *current_session = Some(session);
-                return Err(ParseError::SessionCompletionFailed);
+                leave_metrics,
+            );
+            if metrics.is_none() {
+                session.button_press_count = None;
+                session.seconds_read = session
+                    .duration()
+                    .map(|duration| duration.num_seconds().max(0) as u64);
+                session.pages_turned = None;
             }
-            Ok(Some(session))
+            Some(session)
         }
-        _ => Err(ParseError::InvalidEventType),
+        _ => None,
     }
 }
 
@@ -555,44 +718,40 @@ fn parse_optional_json_value(
     }
 }
 
-fn session_contains(session: &ReadingSession, ts: DateTime<Utc>, tolerance: Duration) -> bool {
-    match session.time_end {
-        Some(end) => ts >= session.time_start - tolerance && ts <= end + tolerance,
-        None => false,
-    }
+trait Timestamped {
+    fn timestamp(&self) -> DateTime<Utc>;
 }
 
-fn find_session_index(
-    sessions: &[CorrelatedSession],
-    ts: DateTime<Utc>,
-    tolerance: Duration,
-) -> Option<usize> {
-    sessions
-        .iter()
-        .position(|session| session_contains(&session.session, ts, tolerance))
+impl Timestamped for AppEvent {
+    fn timestamp(&self) -> DateTime<Utc> {
+        self.timestamp
+    }
 }
 
-fn session_in_cycle(
-    session: &ReadingSession,
-    start: DateTime<Utc>,
-    end: Option<DateTime<Utc>>,
-) -> bool {
-    match end {
-        Some(end) => session.time_start >= start && session.time_start < end,
-        None => session.time_start >= start,
+impl Timestamped for BrightnessEvent {
+    fn timestamp(&self) -> DateTime<Utc> {
+        self.timestamp
     }
 }
 
-fn app_event_in_cycle(event: &AppEvent, start: DateTime<Utc>, end: Option<DateTime<Utc>>) -> bool {
-    match end {
-        Some(end) => event.timestamp >= start && event.timestamp < end,
-        None => event.timestamp >= start,
-    }
+fn events_in_cycle<T: Timestamped>(
+    events: &[T],
+    start: DateTime<Utc>,
+    end: Option<DateTime<Utc>>,
+) -> std::ops::Range<usize> {
+    let lower = events.partition_point(|event| event.timestamp() < start);
+    let upper = match end {
+        Some(end) => events.partition_point(|event| event.timestamp() < end),
+        None => events.len(),
+    };
+    lower..upper.max(lower)
 }
 
 fn compute_cycle_metrics(
     sessions: &[CorrelatedSession],
     app_events: &[AppEvent],
+    brightness: &[BrightnessEvent],
+    end_battery: Option<u8>,
 ) -> ChargeCycleMetrics {
     let total_seconds_read = sessions
         .iter()
@@ -618,6 +777,34 @@ fn compute_cycle_metrics(
         .iter()
         .filter(|event| event.kind == AppEventKind::AppStart)
         .count();
+    let annotations = sessions
+        .iter()
+        .map(|session| session.annotations.len())
+        .sum();
+
+    let mut readings = app_events
+        .iter()
+        .filter(|event| event.kind != AppEventKind::PluggedIn)
+        .filter_map(|event| event.battery_level());
+    let start_battery = readings.next();
+    let end_battery = end_battery.or_else(|| readings.next_back().or(start_battery));
+    let charger = app_events
+        .iter()
+        .filter(|event| event.kind == AppEventKind::PluggedIn)
+        .find_map(|event| event.charger());
+    let battery_drain = match (start_battery, end_battery) {
+        (Some(start), Some(end)) => start.checked_sub(end),
+        _ => None,
+    };
+    let drain_per_reading_hour = battery_drain
+        .filter(|_| total_seconds_read > 0)
+        .map(|drain| drain as f64 / (total_seconds_read as f64 / 3600.0));
+    let projected_reading_hours = drain_per_reading_hour
+        .filter(|drain| *drain > 0.0)
+        .map(|drain| 100.0 / drain);
+    let average_brightness =
+        (!brightness.is_empty()).then(|| BrightnessStats::new(brightness).avg());
+
     ChargeCycleMetrics {
         total_seconds_read,
         total_pages,
@@ -625,40 +812,47 @@ fn compute_cycle_metrics(
         dictionary_lookups,
         brightness_events,
         app_starts,
+        annotations,
+        start_battery,
+        end_battery,
+        charger,
+        battery_drain,
+        drain_per_reading_hour,
+        average_brightness,
+        projected_reading_hours,
     }
 }
 
 fn build_charge_cycles(
     sessions: &[CorrelatedSession],
+    index: &SessionIndex,
     app_events: &[AppEvent],
+    brightness: &[BrightnessEvent],
 ) -> Vec<ChargeCycle> {
-    let mut plug_events: Vec<AppEvent> = app_events
+    let plug_events: Vec<&AppEvent> = app_events
         .iter()
         .filter(|event| event.kind == AppEventKind::PluggedIn)
-        .cloned()
         .collect();
-    plug_events.sort_by_key(|event| event.timestamp);
 
     let mut cycles = Vec::new();
-    for (index, plug_event) in plug_events.iter().enumerate() {
+    for (position, plug_event) in plug_events.iter().enumerate() {
         let start = plug_event.timestamp;
-        let end = plug_events.get(index + 1).map(|event| event.timestamp);
-        let cycle_sessions: Vec<CorrelatedSession> = sessions
-            .iter()
-            .filter(|session| session_in_cycle(&session.session, start, end))
-            .cloned()
-            .collect();
-        let cycle_app_events: Vec<AppEvent> = app_events
-            .iter()
-            .filter(|event| app_event_in_cycle(event, start, end))
-            .cloned()
-            .collect();
-        let metrics = compute_cycle_metrics(&cycle_sessions, &cycle_app_events);
+        let next_plug = plug_events.get(position + 1);
+        let end = next_plug.map(|event| event.timestamp);
+        let session_range = index.starting_in(start, end);
+        let cycle_app_events = &app_events[events_in_cycle(app_events, start, end)];
+        let cycle_brightness = &brightness[events_in_cycle(brightness, start, end)];
+        let metrics = compute_cycle_metrics(
+            &sessions[session_range.clone()],
+            cycle_app_events,
+            cycle_brightness,
+            next_plug.and_then(|event| event.battery_level()),
+        );
         cycles.push(ChargeCycle {
             start,
             end,
-            sessions: cycle_sessions,
-            app_events: cycle_app_events,
+            session_indices: session_range.collect(),
+            app_events: cycle_app_events.to_vec(),
             metrics,
         });
     }
@@ -686,27 +880,63 @@ fn event_types_for_option(option: ParseOption) -> Vec<&'static str> {
     }
 }
 
-fn build_event_query(event_types_len: usize) -> String {
+fn build_event_query(schema: &DbSchema, event_types_len: usize) -> rusqlite::Result<String> {
+    schema.require(
+        "AnalyticsEvents",
+        &["Id", "Type", "Timestamp", "Attributes"],
+    )?;
     let placeholders = std::iter::repeat_n("?", event_types_len)
         .collect::<Vec<_>>()
         .join(", ");
-    format!(
-        "SELECT Id, Type, Timestamp, Attributes, Metrics FROM AnalyticsEvents WHERE Type IN ({}) ORDER BY Timestamp ASC;",
+    Ok(format!(
+        "SELECT Id, Type, Timestamp, Attributes, {} FROM AnalyticsEvents WHERE Type IN ({}) ORDER BY Timestamp ASC;",
+        schema.column_or("AnalyticsEvents", "Metrics", "Metrics", "NULL"),
         placeholders
-    )
+    ))
 }
 
 fn get_books_by_volume_id(
     db: &Connection,
+    schema: &DbSchema,
     volume_ids: &HashSet<String>,
 ) -> rusqlite::Result<HashMap<String, Book>> {
     let mut books = HashMap::new();
-    if volume_ids.is_empty() {
+    if volume_ids.is_empty() || !schema.has_table("content") {
         return Ok(books);
     }
-    let mut stmt = db.prepare(
-        "SELECT BookID, Title, Attribution as Authors FROM content WHERE ContentType=6 AND (ContentID = ?1 OR BookID = ?1)",
-    )?;
+    schema.require("content", &["ContentID", "Title"])?;
+    let shelves = get_shelves_with(db, schema)?;
+    let has_book_id = schema.has_column("content", "BookID");
+    let volume_filter = if has_book_id {
+        "(ContentID = ?1 OR BookID = ?1)"
+    } else {
+        "ContentID = ?1"
+    };
+    let type_filter = if schema.has_column("content", "ContentType") {
+        "ContentType=6 AND "
+    } else {
+        ""
+    };
+    let mut stmt = db.prepare(&format!(
+        "SELECT {}, Title, {}, {}, {}, {}, {} FROM content WHERE {}{}",
+        schema.column_or("content", "BookID", "BookID", "NULL"),
+        if schema.has_column("content", "Attribution") {
+            "COALESCE(Attribution, '') AS Authors"
+        } else {
+            "'' AS Authors"
+        },
+        schema.column_or("content", "ISBN", "NULLIF(ISBN, '')", "NULL"),
+        schema.column_or("content", "Series", "NULLIF(Series, '')", "NULL"),
+        schema.column_or(
+            "content",
+            "SeriesNumber",
+            "NULLIF(SeriesNumber, '')",
+            "NULL"
+        ),
+        schema.column_or("content", "ReadStatus", "ReadStatus", "NULL"),
+        type_filter,
+        volume_filter,
+    ))?;
 
     for volume_id in volume_ids {
         let mut rows = stmt.query([volume_id])?;
@@ -715,7 +945,13 @@ fn get_books_by_volume_id(
             let authors: String = row.get("Authors")?;
             let book_id: Option<String> = row.get("BookID")?;
             let book_id = book_id.unwrap_or_else(|| volume_id.clone());
-            books.insert(volume_id.clone(), Book::new(authors, title, None, book_id));
+            let mut book = Book::new(authors, title, None, book_id);
+            book.isbn = row.get("ISBN")?;
+            book.series = row.get("Series")?;
+            book.series_number = row.get("SeriesNumber")?;
+            book.read_status = row.get("ReadStatus")?;
+            book.shelves = shelves.get(volume_id).cloned().unwrap_or_default();
+            books.insert(volume_id.clone(), book);
         }
     }
 
@@ -725,19 +961,62 @@ fn get_books_by_volume_id(
 #[cfg(test)]
 mod tests {
     use super::{ParseOption, Parser};
-    use crate::AppEventKind;
+    use crate::{AppEventKind, CorrelationConfig, MatchStrategy, SessionCompleteness};
     use chrono::NaiveDate;
     use rusqlite::Connection;
 
     fn setup_test_db() -> Connection {
         let conn = Connection::open_in_memory().unwrap();
         conn.execute_batch(
-            "CREATE TABLE AnalyticsEvents (\n                Id TEXT PRIMARY KEY,\n                Type TEXT NOT NULL,\n                Timestamp TEXT NOT NULL,\n                Attributes TEXT,\n                Metrics TEXT\n            );\n            CREATE TABLE content (\n                ContentID TEXT PRIMARY KEY,\n                ContentType INTEGER,\n                Title TEXT,\n                Attribution TEXT,\n                BookID TEXT\n            );\n            CREATE TABLE Bookmark (\n                BookmarkID TEXT PRIMARY KEY,\n                Text TEXT,\n                VolumeID TEXT,\n                Color INTEGER,\n                ChapterProgress REAL,\n                DateCreated TEXT,\n                DateModified TEXT\n            );",
+            "CREATE TABLE AnalyticsEvents (\n                Id TEXT PRIMARY KEY,\n                Type TEXT NOT NULL,\n                Timestamp TEXT NOT NULL,\n                Attributes TEXT,\n                Metrics TEXT\n            );\n            CREATE TABLE content (\n                ContentID TEXT PRIMARY KEY,\n                ContentType INTEGER,\n                Title TEXT,\n                Attribution TEXT,\n                BookID TEXT,\n                VolumeIndex INTEGER,\n                ISBN TEXT,\n                Series TEXT,\n                SeriesNumber TEXT,\n                ReadStatus INTEGER\n            );\n            CREATE TABLE Bookmark (\n                BookmarkID TEXT PRIMARY KEY,\n                ContentID TEXT,\n                Text TEXT,\n                VolumeID TEXT,\n                Color INTEGER,\n                ChapterProgress REAL,\n                DateCreated TEXT,\n                DateModified TEXT,\n                Annotation TEXT\n            );",
         )
         .unwrap();
         conn
     }
 
+    #[test]
+    fn test_parse_events_old_firmware_schema() {
+        let db = Connection::open_in_memory().unwrap();
+        db.execute_batch(
+            "CREATE TABLE DbVersion (version INTEGER);
+             INSERT INTO DbVersion VALUES (98);
+             CREATE TABLE AnalyticsEvents (Id TEXT PRIMARY KEY, Type TEXT NOT NULL, Timestamp TEXT NOT NULL, Attributes TEXT);
+             CREATE TABLE content (ContentID TEXT PRIMARY KEY, ContentType INTEGER, Title TEXT, Attribution TEXT, BookID TEXT);
+             CREATE TABLE Bookmark (BookmarkID TEXT PRIMARY KEY, ContentID TEXT, Text TEXT, VolumeID TEXT,
+                 ChapterProgress REAL, DateCreated TEXT, DateModified TEXT);
+             INSERT INTO content VALUES ('book1', 6, 'Dune', 'Frank Herbert', NULL);
+             INSERT INTO Bookmark VALUES ('bm1', 'book1!!ch1', 'quote', 'book1', 0.2, '2023-01-01T10:02:00Z', '2023-01-01T10:02:00Z');
+             INSERT INTO AnalyticsEvents VALUES ('open1', 'OpenContent', '2023-01-01T10:00:00Z', '{\"progress\":\"0\",\"volumeid\":\"book1\"}');
+             INSERT INTO AnalyticsEvents VALUES ('leave1', 'LeaveContent', '2023-01-01T10:05:00Z', '{\"progress\":\"10\",\"volumeid\":\"book1\"}');",
+        )
+        .unwrap();
+
+        let analysis = Parser::parse_events(&db, ParseOption::All).unwrap();
+        let sessions = analysis.sessions.unwrap();
+        assert_eq!(sessions.sessions_count(), 1);
+        assert_eq!(sessions.valid_sessions().count(), 1);
+        assert_eq!(sessions.get_sessions()[0].seconds_read, Some(300));
+        assert_eq!(sessions.get_sessions()[0].pages_turned, None);
+        assert!(analysis.brightness_history.is_none());
+        assert!(analysis.natural_light_history.is_none());
+        let books = analysis.books.unwrap();
+        assert_eq!(books[0].title, "Dune");
+        assert_eq!(books[0].isbn, None);
+        let bookmarks = analysis.bookmarks.unwrap();
+        assert_eq!(bookmarks[0].color, 0);
+        assert_eq!(bookmarks[0].book_title, "Dune");
+
+        db.execute_batch(
+            "DROP TABLE AnalyticsEvents; CREATE TABLE AnalyticsEvents (Id TEXT, Timestamp TEXT);",
+        )
+        .unwrap();
+        let err = Parser::parse_correlated(&db).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid column name: AnalyticsEvents.Type, AnalyticsEvents.Attributes not found in this database (DbVersion 98)"
+        );
+    }
+
     #[test]
     fn test_parse_events_all() {
         let db = setup_test_db();
@@ -1101,4 +1380,366 @@ mod tests {
             Some(1)
         );
     }
+
+    #[test]
+    fn test_parse_correlated_app_starts_by_local_day() {
+        let db = setup_test_db();
+        db.execute(
+            "INSERT INTO AnalyticsEvents (Id, Type, Timestamp, Attributes, Metrics) VALUES (?, ?, ?, ?, ?)",
+            [
+                "app_start1",
+                "AppStart",
+                "2023-01-01T23:30:00Z",
+                "{\"app\":\"nickel\"}",
+                "",
+            ],
+        )
+        .unwrap();
+
+        let tz = chrono::FixedOffset::east_opt(2 * 3600).unwrap();
+        let analysis = Parser::parse_correlated_in(&db, &tz).unwrap();
+
+        assert!(!analysis
+            .app_start_counts_by_day
+            .contains_key(&NaiveDate::from_ymd_opt(2023, 1, 1).unwrap()));
+        assert_eq!(
+            analysis
+                .app_start_counts_by_day
+                .get(&NaiveDate::from_ymd_opt(2023, 1, 2).unwrap())
+                .copied(),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn test_parse_correlated_cycle_battery() {
+        let db = setup_test_db();
+        let rows = [
+            (
+                "plug1",
+                "PluggedIn",
+                "2023-01-01T08:00:00Z",
+                "{\"BatteryLevel\":\"20\",\"ChargerType\":\"USB\"}",
+                "",
+            ),
+            (
+                "start1",
+                "AppStart",
+                "2023-01-01T09:59:00Z",
+                "{\"BatteryLevel\":95}",
+                "",
+            ),
+            (
+                "open1",
+                "OpenContent",
+                "2023-01-01T10:00:00Z",
+                "{\"progress\":\"0\",\"volumeid\":\"book1\"}",
+                "",
+            ),
+            (
+                "bright1",
+                "BrightnessAdjusted",
+                "2023-01-01T10:00:30Z",
+                "{\"Method\":\"manual\"}",
+                "{\"NewBrightness\":20}",
+            ),
+            (
+                "bright2",
+                "BrightnessAdjusted",
+                "2023-01-01T11:00:30Z",
+                "{\"Method\":\"manual\"}",
+                "{\"NewBrightness\":40}",
+            ),
+            (
+                "leave1",
+                "LeaveContent",
+                "2023-01-01T12:00:00Z",
+                "{\"progress\":\"30\",\"volumeid\":\"book1\"}",
+                "{\"ButtonPressCount\":0,\"SecondsRead\":7200,\"PagesTurned\":90}",
+            ),
+            (
+                "start2",
+                "AppStart",
+                "2023-01-01T13:00:00Z",
+                "{\"BatteryLevel\":98}",
+                "",
+            ),
+            (
+                "plug2",
+                "PluggedIn",
+                "2023-01-02T08:00:00Z",
+                "{\"BatteryLevel\":\"90\"}",
+                "",
+            ),
+        ];
+        for row in rows {
+            db.execute(
+                "INSERT INTO AnalyticsEvents (Id, Type, Timestamp, Attributes, Metrics) VALUES (?, ?, ?, ?, ?)",
+                [row.0, row.1, row.2, row.3, row.4],
+            )
+            .unwrap();
+        }
+
+        let analysis = Parser::parse_correlated(&db).unwrap();
+        assert_eq!(analysis.cycles.len(), 2);
+        let metrics = &analysis.cycles[0].metrics;
+        assert_eq!(metrics.start_battery, Some(95));
+        assert_eq!(metrics.end_battery, Some(90));
+        assert_eq!(metrics.charger.as_deref(), Some("USB"));
+        assert_eq!(metrics.battery_drain, Some(5));
+        assert_eq!(metrics.drain_per_reading_hour, Some(2.5));
+        assert_eq!(metrics.projected_reading_hours, Some(40.0));
+        assert_eq!(metrics.average_brightness, Some(20.0));
+    }
+
+    #[test]
+    fn test_parse_correlated_with_config() {
+        let db = setup_test_db();
+        let rows = [
+            (
+                "open1",
+                "OpenContent",
+                "2023-01-01T10:00:00Z",
+                "{\"progress\":\"0\",\"volumeid\":\"book1\"}",
+                "",
+            ),
+            (
+                "leave1",
+                "LeaveContent",
+                "2023-01-01T10:10:00Z",
+                "{\"progress\":\"5\",\"volumeid\":\"book1\"}",
+                "{\"ButtonPressCount\":1,\"SecondsRead\":600,\"PagesTurned\":3}",
+            ),
+            (
+                "open2",
+                "OpenContent",
+                "2023-01-01T10:10:20Z",
+                "{\"progress\":\"5\",\"volumeid\":\"book1\"}",
+                "",
+            ),
+            (
+                "dict1",
+                "DictionaryLookup",
+                "2023-01-01T10:10:15Z",
+                "{\"Dictionary\":\"en\",\"Word\":\"edge\"}",
+                "",
+            ),
+            (
+                "start1",
+                "AppStart",
+                "2023-01-01T10:12:00Z",
+                "{\"app\":\"nickel\"}",
+                "",
+            ),
+            (
+                "leave2",
+                "LeaveContent",
+                "2023-01-01T10:20:00Z",
+                "{\"progress\":\"9\",\"volumeid\":\"book1\"}",
+                "{\"ButtonPressCount\":1,\"SecondsRead\":580,\"PagesTurned\":3}",
+            ),
+        ];
+        for row in rows {
+            db.execute(
+                "INSERT INTO AnalyticsEvents (Id, Type, Timestamp, Attributes, Metrics) VALUES (?, ?, ?, ?, ?)",
+                [row.0, row.1, row.2, row.3, row.4],
+            )
+            .unwrap();
+        }
+        db.execute(
+            "INSERT INTO Bookmark (BookmarkID, Text, VolumeID, Color, ChapterProgress, DateCreated, DateModified) VALUES (?, ?, ?, ?, ?, ?, ?)",
+            ["bookmark1", "Some text", "book1", "1", "0.5", "2023-01-01T10:05:00.000", "2023-01-01T10:05:00.000"],
+        )
+        .unwrap();
+        db.execute(
+            "INSERT INTO content (ContentID, Title, ContentType, Attribution, BookID) VALUES (?, ?, ?, ?, ?)",
+            ["book1", "Book One", "6", "Author One", "book1"],
+        )
+        .unwrap();
+
+        let default = Parser::parse_correlated(&db).unwrap();
+        assert_eq!(default.sessions[0].dictionary.len(), 1);
+        assert_eq!(default.sessions[1].app_events.len(), 1);
+        assert_eq!(default.sessions[0].annotations.len(), 1);
+
+        let config = CorrelationConfig {
+            strategy: MatchStrategy::Nearest,
+            attach_app_starts: false,
+            ..Default::default()
+        };
+        let analysis = Parser::parse_correlated_with(&db, &config, &chrono::Utc).unwrap();
+        assert!(analysis.sessions[0].dictionary.is_empty());
+        assert_eq!(analysis.sessions[1].dictionary.len(), 1);
+        assert!(analysis.sessions[1].app_events.is_empty());
+        assert_eq!(analysis.orphans.app_events.len(), 1);
+        assert_eq!(analysis.sessions[0].annotations.len(), 1);
+        assert!(analysis.orphans.annotations.is_empty());
+
+        let config = CorrelationConfig {
+            attach_bookmarks: false,
+            ..Default::default()
+        };
+        let analysis = Parser::parse_correlated_with(&db, &config, &chrono::Utc).unwrap();
+        assert!(analysis.sessions[0].annotations.is_empty());
+        assert!(analysis.orphans.annotations.is_empty());
+    }
+
+    #[test]
+    fn test_parse_correlated_annotations_by_volume() {
+        let db = setup_test_db();
+        let rows = [
+            ("plug1", "PluggedIn", "2023-01-01T09:00:00Z", "", ""),
+            (
+                "open1",
+                "OpenContent",
+                "2023-01-01T10:00:00Z",
+                "{\"progress\":\"0\",\"volumeid\":\"book1\"}",
+                "",
+            ),
+            (
+                "leave1",
+                "LeaveContent",
+                "2023-01-01T10:10:00Z",
+                "{\"progress\":\"5\",\"volumeid\":\"book1\"}",
+                "{\"ButtonPressCount\":1,\"SecondsRead\":600,\"PagesTurned\":3}",
+            ),
+        ];
+        for row in rows {
+            db.execute(
+                "INSERT INTO AnalyticsEvents (Id, Type, Timestamp, Attributes, Metrics) VALUES (?, ?, ?, ?, ?)",
+                [row.0, row.1, row.2, row.3, row.4],
+            )
+            .unwrap();
+        }
+        for (id, volume) in [("bookmark1", "book1"), ("bookmark2", "book2")] {
+            db.execute(
+                "INSERT INTO Bookmark (BookmarkID, Text, VolumeID, Color, ChapterProgress, DateCreated, DateModified) VALUES (?, ?, ?, ?, ?, ?, ?)",
+                [id, "Some text", volume, "1", "0.5", "2023-01-01T10:05:00.000", "2023-01-01T10:05:00.000"],
+            )
+            .unwrap();
+        }
+
+        let analysis = Parser::parse_correlated(&db).unwrap();
+        assert_eq!(analysis.sessions[0].annotations.len(), 1);
+        assert_eq!(analysis.sessions[0].annotations[0].content_id, "bookmark1");
+        assert_eq!(analysis.orphans.annotations.len(), 1);
+        assert_eq!(analysis.orphans.annotations[0].book_title, "");
+        assert_eq!(analysis.cycles[0].metrics.annotations, 1);
+    }
+
+    #[test]
+    fn test_parse_unbalanced_sessions() {
+        let db = setup_test_db();
+        let leave_metrics = "{\"ButtonPressCount\":1,\"SecondsRead\":300,\"PagesTurned\":3}";
+        let rows = [
+            (
+                "open1",
+                "OpenContent",
+                "2023-01-01T10:00:00Z",
+                "{\"progress\":\"0\",\"volumeid\":\"book1\"}",
+                "",
+            ),
+            (
+                "open2",
+                "OpenContent",
+                "2023-01-01T11:00:00Z",
+                "{\"progress\":\"5\",\"volumeid\":\"book1\"}",
+                "",
+            ),
+            (
+                "leave_other",
+                "LeaveContent",
+                "2023-01-01T11:05:00Z",
+                "{\"progress\":\"50\",\"volumeid\":\"book2\"}",
+                leave_metrics,
+            ),
+            (
+                "leave2",
+                "LeaveContent",
+                "2023-01-01T11:10:00Z",
+                "{\"progress\":\"9\",\"volumeid\":\"book1\"}",
+                leave_metrics,
+            ),
+            (
+                "open3",
+                "OpenContent",
+                "2023-01-01T12:00:00Z",
+                "{\"progress\":\"9\",\"volumeid\":\"book1\"}",
+                "",
+            ),
+        ];
+        for row in rows {
+            db.execute(
+                "INSERT INTO AnalyticsEvents (Id, Type, Timestamp, Attributes, Metrics) VALUES (?, ?, ?, ?, ?)",
+                [row.0, row.1, row.2, row.3, row.4],
+            )
+            .unwrap();
+        }
+
+        let analysis = Parser::parse_events(&db, ParseOption::ReadingSessions).unwrap();
+        let sessions = analysis.sessions.unwrap();
+        let statuses: Vec<(&str, SessionCompleteness)> = sessions
+            .get_sessions()
+            .iter()
+            .map(|s| (s.open_content_id.as_str(), s.completeness))
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ("open1", SessionCompleteness::MissingClose),
+                ("", SessionCompleteness::MissingOpen),
+                ("open2", SessionCompleteness::Complete),
+                ("open3", SessionCompleteness::MissingClose),
+            ]
+        );
+        assert_eq!(sessions.valid_sessions().count(), 1);
+        let valid = sessions.valid_sessions().next().unwrap();
+        assert_eq!(valid.leave_content_id.as_deref(), Some("leave2"));
+        assert_eq!(valid.volume_id.as_deref(), Some("book1"));
+    }
+
+    #[test]
+    fn test_session_ids_stable_across_parses() {
+        let db = setup_test_db();
+        db.execute_batch(
+            "INSERT INTO AnalyticsEvents (Id, Type, Timestamp, Attributes, Metrics) VALUES
+                ('open1', 'OpenContent', '2023-01-01T10:00:00Z', '{\"progress\":\"0\",\"volumeid\":\"book1\"}', ''),
+                ('leave1', 'LeaveContent', '2023-01-01T10:05:00Z', '{\"progress\":\"10\",\"volumeid\":\"book1\"}', '{\"ButtonPressCount\":1,\"SecondsRead\":300,\"PagesTurned\":5}');",
+        )
+        .unwrap();
+
+        let session_id = |db: &Connection| {
+            let analysis = Parser::parse_events(db, ParseOption::ReadingSessions).unwrap();
+            analysis.sessions.unwrap().get_sessions()[0].id
+        };
+        let first = session_id(&db);
+        assert_eq!(first, session_id(&db));
+        assert_eq!(
+            first,
+            Parser::parse_correlated(&db).unwrap().sessions[0]
+                .session
+                .id
+        );
+
+        db.execute_batch(
+            "CREATE TABLE user (UserID TEXT, UserKey TEXT);
+             INSERT INTO user (UserID, UserKey) VALUES ('user-1', 'key');",
+        )
+        .unwrap();
+        let namespace = Parser::session_namespace(&db).unwrap();
+        assert_eq!(namespace, crate::account_namespace("user-1"));
+        let on_account = session_id(&db);
+        assert_ne!(first, on_account);
+        assert_eq!(on_account, crate::session_id(&namespace, "open1"));
+
+        db.execute_batch(
+            "ALTER TABLE user ADD COLUMN ___DeviceID TEXT;
+             UPDATE user SET ___DeviceID = 'device-1';",
+        )
+        .unwrap();
+        let namespace = Parser::session_namespace(&db).unwrap();
+        assert_eq!(namespace, crate::device_namespace("device-1"));
+        assert_ne!(namespace, crate::account_namespace("device-1"));
+        assert_eq!(session_id(&db), crate::session_id(&namespace, "open1"));
+    }
 }
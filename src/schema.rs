@@ -0,0 +1,301 @@
+use rusqlite::{Connection, OptionalExtension};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Feature {
+    ReadingSessions,
+    SessionMetrics,
+    LightHistory,
+    Bookmarks,
+    HighlightColors,
+    Annotations,
+    ChapterPositions,
+    BookMetadata,
+    Isbn,
+    Series,
+    ReadStatus,
+    Shelves,
+    DeviceSessionIds,
+    AccountSessionIds,
+}
+
+const FEATURE_REQUIREMENTS: [(Feature, &[(&str, &str)]); 14] = [
+    (
+        Feature::ReadingSessions,
+        &[
+            ("AnalyticsEvents", "Id"),
+            ("AnalyticsEvents", "Type"),
+            ("AnalyticsEvents", "Timestamp"),
+            ("AnalyticsEvents", "Attributes"),
+        ],
+    ),
+    (Feature::SessionMetrics, &[("AnalyticsEvents", "Metrics")]),
+    (Feature::LightHistory, &[("AnalyticsEvents", "Metrics")]),
+    (
+        Feature::Bookmarks,
+        &[
+            ("Bookmark", "BookmarkID"),
+            ("Bookmark", "Text"),
+            ("Bookmark", "VolumeID"),
+        ],
+    ),
+    (Feature::HighlightColors, &[("Bookmark", "Color")]),
+    (Feature::Annotations, &[("Bookmark", "Annotation")]),
+    (
+        Feature::ChapterPositions,
+        &[
+            ("Bookmark", "ContentID"),
+            ("content", "ContentID"),
+            ("content", "BookID"),
+            ("content", "VolumeIndex"),
+        ],
+    ),
+    (
+        Feature::BookMetadata,
+        &[
+            ("content", "ContentID"),
+            ("content", "Title"),
+            ("content", "Attribution"),
+        ],
+    ),
+    (Feature::Isbn, &[("content", "ISBN")]),
+    (
+        Feature::Series,
+        &[("content", "Series"), ("content", "SeriesNumber")],
+    ),
+    (Feature::ReadStatus, &[("content", "ReadStatus")]),
+    (
+        Feature::Shelves,
+        &[("ShelfContent", "ContentId"), ("ShelfContent", "ShelfName")],
+    ),
+    (Feature::DeviceSessionIds, &[("user", "___DeviceID")]),
+    (Feature::AccountSessionIds, &[("user", "UserID")]),
+];
+
+impl Feature {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Feature::ReadingSessions => "reading sessions",
+            Feature::SessionMetrics => "session metrics",
+            Feature::LightHistory => "brightness and natural light history",
+            Feature::Bookmarks => "bookmarks",
+            Feature::HighlightColors => "highlight colors",
+            Feature::Annotations => "annotations",
+            Feature::ChapterPositions => "chapter positions",
+            Feature::BookMetadata => "book metadata",
+            Feature::Isbn => "ISBN",
+            Feature::Series => "series",
+            Feature::ReadStatus => "read status",
+            Feature::Shelves => "shelves",
+            Feature::DeviceSessionIds => "device session IDs",
+            Feature::AccountSessionIds => "account session IDs",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FeatureStatus {
+    pub feature: Feature,
+    pub missing: Vec<String>,
+}
+
+impl FeatureStatus {
+    pub fn available(&self) -> bool {
+        self.missing.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FeatureReport {
+    pub db_version: Option<i64>,
+    pub features: Vec<FeatureStatus>,
+}
+
+impl FeatureReport {
+    pub fn is_available(&self, feature: Feature) -> bool {
+        self.features
+            .iter()
+            .any(|status| status.feature == feature && status.available())
+    }
+}
+
+impl fmt::Display for FeatureReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.db_version {
+            Some(version) => writeln!(f, "DbVersion: {}", version)?,
+            None => writeln!(f, "DbVersion: unknown")?,
+        }
+        for status in &self.features {
+            if status.available() {
+                writeln!(f, "[x] {}", status.feature.as_str())?;
+            } else {
+                writeln!(
+                    f,
+                    "[ ] {} (missing {})",
+                    status.feature.as_str(),
+                    status.missing.join(", ")
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DbSchema {
+    pub db_version: Option<i64>,
+    tables: HashMap<String, HashSet<String>>,
+}
+
+impl DbSchema {
+    pub fn detect(db: &Connection) -> rusqlite::Result<Self> {
+        let mut tables = HashMap::new();
+        let mut stmt = db.prepare("SELECT name FROM sqlite_master WHERE type = 'table'")?;
+        let names = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        let mut columns = db.prepare("SELECT name FROM pragma_table_info(?1)")?;
+        for name in names {
+            let table_columns = columns
+                .query_map([&name], |row| row.get::<_, String>(0))?
+                .map(|column| column.map(|column| column.to_ascii_lowercase()))
+                .collect::<rusqlite::Result<HashSet<_>>>()?;
+            tables.insert(name.to_ascii_lowercase(), table_columns);
+        }
+
+        let db_version = if tables.contains_key("dbversion") {
+            db.query_row("SELECT * FROM DbVersion LIMIT 1", [], |row| {
+                row.get::<_, Option<i64>>(0)
+            })
+            .optional()?
+            .flatten()
+        } else {
+            None
+        };
+        Ok(Self { db_version, tables })
+    }
+
+    pub fn has_table(&self, table: &str) -> bool {
+        self.tables.contains_key(&table.to_ascii_lowercase())
+    }
+
+    pub fn has_column(&self, table: &str, column: &str) -> bool {
+        self.tables
+            .get(&table.to_ascii_lowercase())
+            .is_some_and(|columns| columns.contains(&column.to_ascii_lowercase()))
+    }
+
+    pub fn column_or(&self, table: &str, column: &str, expr: &str, fallback: &str) -> String {
+        if self.has_column(table, column) {
+            format!("{} AS {}", expr, column)
+        } else {
+            format!("{} AS {}", fallback, column)
+        }
+    }
+
+    pub fn require(&self, table: &str, columns: &[&str]) -> rusqlite::Result<()> {
+        let missing: Vec<String> = columns
+            .iter()
+            .filter(|column| !self.has_column(table, column))
+            .map(|column| format!("{}.{}", table, column))
+            .collect();
+        if missing.is_empty() {
+            return Ok(());
+        }
+        let version = self
+            .db_version
+            .map(|version| version.to_string())
+            .unwrap_or_else(|| "unknown".to_string());
+        Err(rusqlite::Error::InvalidColumnName(format!(
+            "{} not found in this database (DbVersion {})",
+            missing.join(", "),
+            version
+        )))
+    }
+
+    pub fn features(&self) -> FeatureReport {
+        let features = FEATURE_REQUIREMENTS
+            .iter()
+            .map(|(feature, requirements)| FeatureStatus {
+                feature: *feature,
+                missing: requirements
+                    .iter()
+                    .filter(|(table, column)| !self.has_column(table, column))
+                    .map(|(table, column)| format!("{}.{}", table, column))
+                    .collect(),
+            })
+            .collect();
+        FeatureReport {
+            db_version: self.db_version,
+            features,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn old_firmware_db() -> Connection {
+        let db = Connection::open_in_memory().unwrap();
+        db.execute_batch(
+            "CREATE TABLE DbVersion (version INTEGER);
+             INSERT INTO DbVersion VALUES (98);
+             CREATE TABLE AnalyticsEvents (Id TEXT, Type TEXT, Timestamp TEXT, Attributes TEXT);
+             CREATE TABLE content (ContentID TEXT, ContentType INTEGER, Title TEXT, Attribution TEXT, BookID TEXT);
+             CREATE TABLE Bookmark (BookmarkID TEXT, VolumeID TEXT, ContentID TEXT, Text TEXT,
+                 ChapterProgress REAL, DateCreated TEXT, DateModified TEXT);",
+        )
+        .unwrap();
+        db
+    }
+
+    #[test]
+    fn test_detect_schema() {
+        let schema = DbSchema::detect(&old_firmware_db()).unwrap();
+        assert_eq!(schema.db_version, Some(98));
+        assert!(schema.has_table("bookmark"));
+        assert!(schema.has_column("Bookmark", "bookmarkid"));
+        assert!(!schema.has_column("Bookmark", "Color"));
+        assert_eq!(
+            schema.column_or("Bookmark", "Color", "bm.Color", "0"),
+            "0 AS Color"
+        );
+        assert_eq!(
+            schema.column_or("Bookmark", "Text", "bm.Text", "''"),
+            "bm.Text AS Text"
+        );
+
+        let err = schema
+            .require("Bookmark", &["Text", "Color", "Annotation"])
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid column name: Bookmark.Color, Bookmark.Annotation not found in this database (DbVersion 98)"
+        );
+
+        assert_eq!(
+            DbSchema::detect(&Connection::open_in_memory().unwrap())
+                .unwrap()
+                .db_version,
+            None
+        );
+    }
+
+    #[test]
+    fn test_feature_report() {
+        let report = DbSchema::detect(&old_firmware_db()).unwrap().features();
+        assert!(report.is_available(Feature::ReadingSessions));
+        assert!(report.is_available(Feature::Bookmarks));
+        assert!(!report.is_available(Feature::HighlightColors));
+        assert!(!report.is_available(Feature::SessionMetrics));
+        assert!(!report.is_available(Feature::LightHistory));
+        let text = report.to_string();
+        assert!(text.starts_with("DbVersion: 98\n[x] reading sessions\n"));
+        assert!(text.contains("[ ] highlight colors (missing Bookmark.Color)\n"));
+        assert!(text.contains("[ ] chapter positions (missing content.VolumeIndex)\n"));
+    }
+}
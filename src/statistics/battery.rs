@@ -0,0 +1,85 @@
+use crate::ChargeCycle;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BatteryModel {
+    pub intercept: f64,
+    pub slope: f64,
+    pub samples: usize,
+}
+
+impl BatteryModel {
+    pub fn fit(cycles: &[ChargeCycle]) -> Option<Self> {
+        let points: Vec<(f64, f64)> = cycles
+            .iter()
+            .filter_map(|cycle| {
+                let drain = cycle.metrics.drain_per_reading_hour?;
+                let brightness = cycle.metrics.average_brightness?;
+                Some((brightness, drain))
+            })
+            .collect();
+        if points.is_empty() {
+            return None;
+        }
+
+        let n = points.len() as f64;
+        let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+        let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+        let variance: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+        let covariance: f64 = points
+            .iter()
+            .map(|(x, y)| (x - mean_x) * (y - mean_y))
+            .sum();
+        let slope = if variance > 0.0 {
+            covariance / variance
+        } else {
+            0.0
+        };
+
+        Some(Self {
+            intercept: mean_y - slope * mean_x,
+            slope,
+            samples: points.len(),
+        })
+    }
+
+    pub fn drain_per_hour(&self, brightness: f64) -> f64 {
+        self.intercept + self.slope * brightness
+    }
+
+    pub fn projected_reading_hours(&self, brightness: f64) -> Option<f64> {
+        let drain = self.drain_per_hour(brightness);
+        (drain > 0.0).then(|| 100.0 / drain)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ChargeCycleMetrics;
+    use chrono::Utc;
+
+    fn cycle(brightness: f64, drain: f64) -> ChargeCycle {
+        ChargeCycle {
+            start: Utc::now(),
+            end: None,
+            session_indices: Vec::new(),
+            app_events: Vec::new(),
+            metrics: ChargeCycleMetrics {
+                average_brightness: Some(brightness),
+                drain_per_reading_hour: Some(drain),
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn test_fit_and_project() {
+        let cycles = [cycle(20.0, 4.0), cycle(60.0, 8.0), cycle(40.0, 6.0)];
+        let model = BatteryModel::fit(&cycles).unwrap();
+        assert_eq!(model.samples, 3);
+        assert!((model.slope - 0.1).abs() < 1e-9);
+        assert!((model.drain_per_hour(40.0) - 6.0).abs() < 1e-9);
+        assert!((model.projected_reading_hours(30.0).unwrap() - 20.0).abs() < 1e-9);
+        assert!(BatteryModel::fit(&[]).is_none());
+    }
+}
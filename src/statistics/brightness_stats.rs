@@ -0,0 +1,235 @@
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+
+use crate::{AdjustmentMethod, BrightnessEvent, ReadingSession};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MethodBreakdown {
+    pub manual: usize,
+    pub automatic: usize,
+    pub other: usize,
+}
+
+impl MethodBreakdown {
+    pub fn total(&self) -> usize {
+        self.manual + self.automatic + self.other
+    }
+
+    pub fn manual_share(&self) -> f64 {
+        match self.total() {
+            0 => 0.0,
+            total => self.manual as f64 / total as f64,
+        }
+    }
+}
+
+pub struct BrightnessStats<'a> {
+    events: &'a [BrightnessEvent],
+}
+
+impl<'a> BrightnessStats<'a> {
+    pub fn new(events: &'a [BrightnessEvent]) -> Self {
+        Self { events }
+    }
+
+    fn weighted_levels(&self) -> Vec<(u8, f64)> {
+        self.events
+            .windows(2)
+            .filter_map(|pair| {
+                let duration = (pair[1].timestamp - pair[0].timestamp).num_seconds() as f64;
+                (duration > 0.0).then_some((pair[0].brightness.percentage, duration))
+            })
+            .collect()
+    }
+
+    pub fn avg(&self) -> f64 {
+        let levels = self.weighted_levels();
+        let total_duration: f64 = levels.iter().map(|(_, duration)| duration).sum();
+        if total_duration == 0.0 {
+            return self
+                .events
+                .last()
+                .map_or(0.0, |e| e.brightness.percentage as f64);
+        }
+        let weighted_sum: f64 = levels
+            .iter()
+            .map(|(level, duration)| *level as f64 * duration)
+            .sum();
+        weighted_sum / total_duration
+    }
+
+    pub fn percentiles(&self, percentiles: &[f64]) -> Vec<f64> {
+        if self.events.is_empty() {
+            return vec![0.0; percentiles.len()];
+        }
+
+        let mut levels = self.weighted_levels();
+        if levels.is_empty() {
+            levels = self
+                .events
+                .iter()
+                .map(|e| (e.brightness.percentage, 1.0))
+                .collect();
+        }
+        levels.sort_by_key(|(level, _)| *level);
+        let total: f64 = levels.iter().map(|(_, weight)| weight).sum();
+
+        percentiles
+            .iter()
+            .map(|&p| {
+                let threshold = p.clamp(0.0, 1.0) * total;
+                let mut cumulative = 0.0;
+                for (level, weight) in &levels {
+                    cumulative += weight;
+                    if cumulative >= threshold {
+                        return *level as f64;
+                    }
+                }
+                levels.last().map_or(0.0, |(level, _)| *level as f64)
+            })
+            .collect()
+    }
+
+    pub fn time_at_level(&self) -> BTreeMap<u8, f64> {
+        let mut histogram = BTreeMap::new();
+        for (level, duration) in self.weighted_levels() {
+            *histogram.entry(level).or_insert(0.0) += duration;
+        }
+        histogram
+    }
+
+    pub fn time_at_level_buckets(&self, bucket_width: u8) -> BTreeMap<u8, f64> {
+        let width = bucket_width.max(1);
+        let mut histogram = BTreeMap::new();
+        for (level, duration) in self.weighted_levels() {
+            *histogram.entry(level / width * width).or_insert(0.0) += duration;
+        }
+        histogram
+    }
+
+    pub fn adjustments_per_reading_hour<'s>(
+        &self,
+        sessions: impl IntoIterator<Item = &'s ReadingSession>,
+    ) -> f64 {
+        let mut seconds_read = 0;
+        let mut spans = Vec::new();
+        for session in sessions {
+            let Some(end) = session.time_end else {
+                continue;
+            };
+            seconds_read += session.seconds_read.unwrap_or(0);
+            spans.push((session.time_start, end));
+        }
+        spans.sort_unstable();
+
+        // Merge overlapping sessions so an adjustment is counted only once.
+        let mut merged: Vec<(DateTime<Utc>, DateTime<Utc>)> = Vec::with_capacity(spans.len());
+        for (start, end) in spans {
+            match merged.last_mut() {
+                Some(last) if start <= last.1 => last.1 = last.1.max(end),
+                _ => merged.push((start, end)),
+            }
+        }
+
+        let adjustments = self
+            .events
+            .iter()
+            .filter(|e| {
+                let index = merged.partition_point(|(start, _)| *start <= e.timestamp);
+                index > 0 && e.timestamp <= merged[index - 1].1
+            })
+            .count();
+        if seconds_read == 0 {
+            0.0
+        } else {
+            adjustments as f64 / (seconds_read as f64 / 3600.0)
+        }
+    }
+
+    pub fn method_breakdown(&self) -> MethodBreakdown {
+        let mut breakdown = MethodBreakdown::default();
+        for event in self.events {
+            match event.brightness.adjustment_method() {
+                AdjustmentMethod::Manual => breakdown.manual += 1,
+                AdjustmentMethod::Automatic => breakdown.automatic += 1,
+                AdjustmentMethod::Other => breakdown.other += 1,
+            }
+        }
+        breakdown
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Brightness, BrightnessHistory, NaturalLightHistory, Statistics};
+    use std::str::FromStr;
+
+    fn event(method: &str, level: u8, ts: &str) -> BrightnessEvent {
+        BrightnessEvent::new(
+            Brightness::new(method.to_string(), level),
+            DateTime::<Utc>::from_str(ts).unwrap(),
+        )
+    }
+
+    fn events() -> Vec<BrightnessEvent> {
+        vec![
+            event("manual", 10, "2023-01-01T10:00:00Z"),
+            event("auto", 80, "2023-01-01T10:30:00Z"),
+            event("manual", 40, "2023-01-01T10:40:00Z"),
+        ]
+    }
+
+    #[test]
+    fn test_time_weighted_percentiles() {
+        let history = BrightnessHistory { events: events() };
+        // 10% held for 30 min, 80% held for 10 min
+        assert_eq!(
+            history.calculate_percentile((), &[0.5, 0.9]),
+            vec![10.0, 80.0]
+        );
+        assert_eq!(history.avg(), (10.0 * 1800.0 + 80.0 * 600.0) / 2400.0);
+
+        let natural = NaturalLightHistory { events: events() };
+        assert_eq!(natural.avg(), history.avg());
+        assert!(BrightnessHistory::new()
+            .calculate_percentile((), &[0.5])
+            .eq(&[0.0]));
+    }
+
+    #[test]
+    fn test_histogram_and_breakdown() {
+        let events = events();
+        let stats = BrightnessStats::new(&events);
+        let histogram = stats.time_at_level();
+        assert_eq!(histogram.get(&10), Some(&1800.0));
+        assert_eq!(histogram.get(&80), Some(&600.0));
+        assert_eq!(stats.time_at_level_buckets(25).get(&75), Some(&600.0));
+
+        let breakdown = stats.method_breakdown();
+        assert_eq!(breakdown.manual, 2);
+        assert_eq!(breakdown.automatic, 1);
+    }
+
+    #[test]
+    fn test_adjustments_per_reading_hour() {
+        let events = events();
+        let start = DateTime::<Utc>::from_str("2023-01-01T10:00:00Z").unwrap();
+        let end = DateTime::<Utc>::from_str("2023-01-01T10:30:00Z").unwrap();
+        let mut session = ReadingSession::new(start, 0, None, None, "open".to_string());
+        session
+            .complete_session(end, 10, 0, 1800, 10, "leave".to_string())
+            .unwrap();
+        let stats = BrightnessStats::new(&events);
+        assert_eq!(stats.adjustments_per_reading_hour([&session]), 4.0);
+
+        let mut overlapping = ReadingSession::new(start, 0, None, None, "open-overlap".to_string());
+        overlapping
+            .complete_session(end, 10, 0, 1800, 10, "leave".to_string())
+            .unwrap();
+        assert_eq!(
+            stats.adjustments_per_reading_hour([&overlapping, &session]),
+            2.0
+        );
+    }
+}
@@ -0,0 +1,379 @@
+use chrono::{Datelike, Duration, NaiveDate, TimeZone};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::statistics::timeseries::{local_date, Granularity};
+use crate::{Book, ReadingSessions, MIN_VALID_SESSION_TIME};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Goal {
+    MinutesPerDay(u64),
+    DaysPerWeek(u32),
+    BooksPerYear(u32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Streaks {
+    pub current: u32,
+    pub longest: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GoalProgress {
+    pub goal: Goal,
+    pub period_start: NaiveDate,
+    pub period_end: NaiveDate,
+    pub current: f64,
+    pub target: f64,
+    pub expected: f64,
+}
+
+impl GoalProgress {
+    pub fn is_met(&self) -> bool {
+        self.current >= self.target
+    }
+
+    pub fn is_on_track(&self) -> bool {
+        self.current >= self.expected
+    }
+
+    pub fn ratio(&self) -> f64 {
+        if self.target <= 0.0 {
+            1.0
+        } else {
+            self.current / self.target
+        }
+    }
+}
+
+pub struct GoalsEngine<'a, Tz: TimeZone> {
+    sessions: &'a ReadingSessions,
+    books: &'a [Book],
+    tz: Tz,
+    min_session_seconds: u64,
+}
+
+impl<'a, Tz: TimeZone> GoalsEngine<'a, Tz> {
+    pub fn new(sessions: &'a ReadingSessions, tz: Tz) -> Self {
+        Self {
+            sessions,
+            books: &[],
+            tz,
+            min_session_seconds: MIN_VALID_SESSION_TIME,
+        }
+    }
+
+    /// Books marked as finished on the device count as finished on the day
+    /// they were last read, even if no session reached 100%.
+    pub fn with_books(mut self, books: &'a [Book]) -> Self {
+        self.books = books;
+        self
+    }
+
+    pub fn with_min_session_seconds(mut self, min_session_seconds: u64) -> Self {
+        self.min_session_seconds = min_session_seconds;
+        self
+    }
+
+    pub fn seconds_by_day(&self) -> BTreeMap<NaiveDate, u64> {
+        let mut days = BTreeMap::new();
+        for session in self
+            .sessions
+            .valid_sessions_with_min(self.min_session_seconds)
+        {
+            let day = local_date(session.time_start, &self.tz);
+            *days.entry(day).or_insert(0) += session.seconds_read.unwrap_or(0);
+        }
+        days
+    }
+
+    pub fn streaks(&self, as_of: NaiveDate) -> Streaks {
+        let days = self.seconds_by_day();
+        let mut longest = 0;
+        let mut run = 0;
+        let mut previous: Option<NaiveDate> = None;
+        for day in days.keys().filter(|day| **day <= as_of) {
+            run = match previous {
+                Some(prev) if *day - prev == Duration::days(1) => run + 1,
+                _ => 1,
+            };
+            longest = longest.max(run);
+            previous = Some(*day);
+        }
+
+        let yesterday = as_of - Duration::days(1);
+        let current = match previous {
+            Some(last) if last == as_of || last == yesterday => run,
+            _ => 0,
+        };
+        Streaks { current, longest }
+    }
+
+    pub fn days_read_per_week(&self) -> BTreeMap<NaiveDate, u32> {
+        let mut weeks = BTreeMap::new();
+        for day in self.seconds_by_day().keys() {
+            *weeks
+                .entry(Granularity::IsoWeekly.bucket_start(*day))
+                .or_insert(0) += 1;
+        }
+        weeks
+    }
+
+    pub fn books_finished_by_day(&self) -> BTreeMap<NaiveDate, u32> {
+        self.books_finished_between(NaiveDate::MIN, NaiveDate::MAX)
+    }
+
+    /// Counts each book once, on its first finish between `start` and `end`,
+    /// so rereads count in the period they were finished again.
+    pub fn books_finished_between(
+        &self,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> BTreeMap<NaiveDate, u32> {
+        let mut finished: HashMap<&str, NaiveDate> = HashMap::new();
+        let mut last_read: HashMap<&str, NaiveDate> = HashMap::new();
+        for session in self
+            .sessions
+            .valid_sessions_with_min(self.min_session_seconds)
+        {
+            let Some(key) = session
+                .volume_id
+                .as_deref()
+                .or(session.book_title.as_deref())
+            else {
+                continue;
+            };
+            let day = local_date(session.time_end.unwrap_or(session.time_start), &self.tz);
+            last_read
+                .entry(key)
+                .and_modify(|existing| *existing = (*existing).max(day))
+                .or_insert(day);
+            if session.end_progress != Some(100) || day < start || day > end {
+                continue;
+            }
+            finished
+                .entry(key)
+                .and_modify(|existing| *existing = (*existing).min(day))
+                .or_insert(day);
+        }
+
+        for book in self.books.iter().filter(|book| book.is_finished()) {
+            let keys = [book.book_id.as_str(), book.title.as_str()];
+            if keys.iter().any(|key| finished.contains_key(key)) {
+                continue;
+            }
+            let Some((key, day)) = keys
+                .iter()
+                .find_map(|key| last_read.get(key).map(|day| (*key, *day)))
+            else {
+                continue;
+            };
+            if day >= start && day <= end {
+                finished.insert(key, day);
+            }
+        }
+
+        let mut days = BTreeMap::new();
+        for day in finished.into_values() {
+            *days.entry(day).or_insert(0) += 1;
+        }
+        days
+    }
+
+    pub fn evaluate(&self, goal: Goal, as_of: NaiveDate) -> GoalProgress {
+        match goal {
+            Goal::MinutesPerDay(minutes) => {
+                let seconds = self.seconds_by_day().get(&as_of).copied().unwrap_or(0);
+                let target = minutes as f64;
+                GoalProgress {
+                    goal,
+                    period_start: as_of,
+                    period_end: as_of,
+                    current: seconds as f64 / 60.0,
+                    target,
+                    expected: target,
+                }
+            }
+            Goal::DaysPerWeek(days) => {
+                let start = Granularity::IsoWeekly.bucket_start(as_of);
+                let end = start + Duration::days(6);
+                let read = self.seconds_by_day().range(start..=as_of).count();
+                let elapsed = (as_of - start).num_days() + 1;
+                let target = days as f64;
+                GoalProgress {
+                    goal,
+                    period_start: start,
+                    period_end: end,
+                    current: read as f64,
+                    target,
+                    expected: target * elapsed as f64 / 7.0,
+                }
+            }
+            Goal::BooksPerYear(books) => {
+                let start = NaiveDate::from_ymd_opt(as_of.year(), 1, 1).unwrap_or(as_of);
+                let end = NaiveDate::from_ymd_opt(as_of.year(), 12, 31).unwrap_or(as_of);
+                let finished: u32 = self.books_finished_between(start, as_of).values().sum();
+                let elapsed = as_of.ordinal() as f64;
+                let year_len = end.ordinal() as f64;
+                let target = books as f64;
+                GoalProgress {
+                    goal,
+                    period_start: start,
+                    period_end: end,
+                    current: finished as f64,
+                    target,
+                    expected: target * elapsed / year_len,
+                }
+            }
+        }
+    }
+
+    pub fn evaluate_all(&self, goals: &[Goal], as_of: NaiveDate) -> Vec<GoalProgress> {
+        goals
+            .iter()
+            .map(|goal| self.evaluate(*goal, as_of))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ReadingSession;
+    use chrono::{DateTime, Utc};
+    use std::str::FromStr;
+
+    fn add(sessions: &mut ReadingSessions, start: &str, seconds: u64, progress: (u8, u8)) {
+        add_book(sessions, "book1", start, seconds, progress);
+    }
+
+    fn add_book(
+        sessions: &mut ReadingSessions,
+        volume_id: &str,
+        start: &str,
+        seconds: u64,
+        progress: (u8, u8),
+    ) {
+        let start = DateTime::<Utc>::from_str(start).unwrap();
+        let mut session = ReadingSession::new(
+            start,
+            progress.0,
+            Some(volume_id.to_string()),
+            Some(volume_id.to_string()),
+            "open".to_string(),
+        );
+        session
+            .complete_session(
+                start + Duration::seconds(seconds as i64),
+                progress.1,
+                0,
+                seconds,
+                1,
+                "leave".to_string(),
+            )
+            .unwrap();
+        sessions.add_session(session);
+    }
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2023, 1, d).unwrap()
+    }
+
+    #[test]
+    fn test_streaks() {
+        let mut sessions = ReadingSessions::new();
+        add(&mut sessions, "2023-01-01T10:00:00Z", 600, (0, 10));
+        add(&mut sessions, "2023-01-02T10:00:00Z", 600, (10, 20));
+        add(&mut sessions, "2023-01-03T10:00:00Z", 600, (20, 30));
+        add(&mut sessions, "2023-01-05T10:00:00Z", 600, (30, 40));
+        add(&mut sessions, "2023-01-06T10:00:00Z", 30, (40, 41));
+
+        let engine = GoalsEngine::new(&sessions, Utc);
+        assert_eq!(
+            engine.streaks(day(6)),
+            Streaks {
+                current: 1,
+                longest: 3
+            }
+        );
+        assert_eq!(engine.streaks(day(8)).current, 0);
+
+        let lenient = GoalsEngine::new(&sessions, Utc).with_min_session_seconds(10);
+        assert_eq!(lenient.streaks(day(6)).current, 2);
+    }
+
+    #[test]
+    fn test_goal_progress() {
+        let mut sessions = ReadingSessions::new();
+        add(&mut sessions, "2023-01-02T10:00:00Z", 1200, (0, 50));
+        add(&mut sessions, "2023-01-03T10:00:00Z", 1800, (50, 100));
+
+        let engine = GoalsEngine::new(&sessions, Utc);
+        let progress = engine.evaluate_all(
+            &[
+                Goal::MinutesPerDay(30),
+                Goal::DaysPerWeek(5),
+                Goal::BooksPerYear(20),
+            ],
+            day(3),
+        );
+
+        assert!(progress[0].is_met());
+        assert_eq!(progress[1].current, 2.0);
+        assert_eq!(progress[1].period_start, day(2));
+        assert!(progress[1].is_on_track());
+        assert_eq!(progress[2].current, 1.0);
+        assert!(progress[2].is_on_track());
+        assert!(!progress[2].is_met());
+    }
+
+    #[test]
+    fn test_books_finished_counts_rereads_and_read_status() {
+        let mut sessions = ReadingSessions::new();
+        add_book(
+            &mut sessions,
+            "book1",
+            "2022-03-01T10:00:00Z",
+            600,
+            (90, 100),
+        );
+        add_book(
+            &mut sessions,
+            "book1",
+            "2023-01-02T10:00:00Z",
+            600,
+            (90, 100),
+        );
+        add_book(
+            &mut sessions,
+            "book2",
+            "2023-01-02T12:00:00Z",
+            20,
+            (99, 100),
+        );
+        add_book(
+            &mut sessions,
+            "book3",
+            "2023-01-03T10:00:00Z",
+            600,
+            (90, 99),
+        );
+
+        let engine = GoalsEngine::new(&sessions, Utc);
+        let progress = engine.evaluate(Goal::BooksPerYear(10), day(3));
+        assert_eq!(progress.current, 1.0);
+        assert_eq!(engine.books_finished_by_day().values().sum::<u32>(), 1);
+
+        let mut book = Book::new(
+            String::new(),
+            "book3".to_string(),
+            None,
+            "book3".to_string(),
+        );
+        book.read_status = Some(crate::READ_STATUS_FINISHED);
+        let books = [book];
+        let engine = GoalsEngine::new(&sessions, Utc).with_books(&books);
+        let finished = engine.books_finished_between(day(1), day(3));
+        assert_eq!(finished.get(&day(2)), Some(&1));
+        assert_eq!(finished.get(&day(3)), Some(&1));
+    }
+}
@@ -0,0 +1,213 @@
+use chrono::{DateTime, Datelike, Duration, TimeZone, Timelike, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+
+use crate::{CorrelatedAnalysis, ReadingSession, ReadingSessions};
+
+pub const WEEKDAYS: [Weekday; 7] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+    Weekday::Sat,
+    Weekday::Sun,
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReadingHeatmap {
+    pub reading_seconds: [[f64; 24]; 7],
+    pub brightness_adjustments: [[u32; 24]; 7],
+}
+
+impl Default for ReadingHeatmap {
+    fn default() -> Self {
+        Self {
+            reading_seconds: [[0.0; 24]; 7],
+            brightness_adjustments: [[0; 24]; 7],
+        }
+    }
+}
+
+fn cell<Tz: TimeZone>(ts: DateTime<Utc>, tz: &Tz) -> (usize, usize) {
+    let local = ts.with_timezone(tz);
+    (
+        local.weekday().num_days_from_monday() as usize,
+        local.hour() as usize,
+    )
+}
+
+impl ReadingHeatmap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_sessions<Tz: TimeZone>(sessions: &ReadingSessions, tz: &Tz) -> Self {
+        let mut heatmap = Self::new();
+        for session in sessions.valid_sessions() {
+            heatmap.add_session(session, tz);
+        }
+        heatmap
+    }
+
+    pub fn from_correlated<Tz: TimeZone>(analysis: &CorrelatedAnalysis, tz: &Tz) -> Self {
+        let mut heatmap = Self::new();
+        for correlated in &analysis.sessions {
+            if correlated.session.is_valid() {
+                heatmap.add_session(&correlated.session, tz);
+            }
+        }
+        let session_events = analysis.sessions.iter().flat_map(|session| {
+            session
+                .brightness
+                .iter()
+                .chain(session.natural_light.iter())
+        });
+        let orphan_events = analysis
+            .orphans
+            .brightness
+            .iter()
+            .chain(analysis.orphans.natural_light.iter());
+        for event in session_events.chain(orphan_events) {
+            let (day, hour) = cell(event.timestamp, tz);
+            heatmap.brightness_adjustments[day][hour] += 1;
+        }
+        heatmap
+    }
+
+    pub fn add_session<Tz: TimeZone>(&mut self, session: &ReadingSession, tz: &Tz) {
+        let seconds_read = session.seconds_read.unwrap_or(0) as f64;
+        let Some(end) = session.time_end else {
+            return;
+        };
+        let span = (end - session.time_start).num_seconds();
+        if span <= 0 {
+            let (day, hour) = cell(session.time_start, tz);
+            self.reading_seconds[day][hour] += seconds_read;
+            return;
+        }
+
+        let mut cursor = session.time_start;
+        while cursor < end {
+            let local = cursor.with_timezone(tz);
+            let into_hour = (local.minute() * 60 + local.second()) as i64;
+            let next = (cursor + Duration::seconds(3600 - into_hour)).min(end);
+            let share = (next - cursor).num_seconds() as f64 / span as f64;
+            let (day, hour) = cell(cursor, tz);
+            self.reading_seconds[day][hour] += seconds_read * share;
+            cursor = next;
+        }
+    }
+
+    pub fn total_seconds(&self) -> f64 {
+        self.reading_seconds.iter().flatten().sum()
+    }
+
+    pub fn max_seconds(&self) -> f64 {
+        self.reading_seconds
+            .iter()
+            .flatten()
+            .copied()
+            .fold(0.0, f64::max)
+    }
+
+    pub fn seconds_by_hour(&self) -> [f64; 24] {
+        let mut hours = [0.0; 24];
+        for day in &self.reading_seconds {
+            for (hour, seconds) in day.iter().enumerate() {
+                hours[hour] += seconds;
+            }
+        }
+        hours
+    }
+
+    pub fn adjustments_by_hour(&self) -> [u32; 24] {
+        let mut hours = [0; 24];
+        for day in &self.brightness_adjustments {
+            for (hour, count) in day.iter().enumerate() {
+                hours[hour] += count;
+            }
+        }
+        hours
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CorrelatedSession, OrphanEvents};
+    use chrono::FixedOffset;
+    use std::collections::HashMap;
+    use std::str::FromStr;
+
+    fn session(start: &str, end: &str, seconds: u64) -> ReadingSession {
+        let start = DateTime::<Utc>::from_str(start).unwrap();
+        let end = DateTime::<Utc>::from_str(end).unwrap();
+        let mut session = ReadingSession::new(start, 0, None, None, "open".to_string());
+        session
+            .complete_session(end, 10, 0, seconds, 1, "leave".to_string())
+            .unwrap();
+        session
+    }
+
+    #[test]
+    fn test_session_split_across_hours() {
+        let mut heatmap = ReadingHeatmap::new();
+        // Monday 2023-01-02, 21:30 -> 22:30 UTC
+        heatmap.add_session(
+            &session("2023-01-02T21:30:00Z", "2023-01-02T22:30:00Z", 3000),
+            &Utc,
+        );
+        assert_eq!(heatmap.reading_seconds[0][21], 1500.0);
+        assert_eq!(heatmap.reading_seconds[0][22], 1500.0);
+        assert_eq!(heatmap.total_seconds(), 3000.0);
+    }
+
+    #[test]
+    fn test_from_correlated_matches_valid_sessions() {
+        let mut sessions = ReadingSessions::new();
+        sessions.add_session(session(
+            "2023-01-02T21:00:00Z",
+            "2023-01-02T21:20:00Z",
+            1200,
+        ));
+        sessions.add_session(session("2023-01-02T22:00:00Z", "2023-01-02T22:00:30Z", 30));
+        sessions.add_session(ReadingSession::new(
+            DateTime::<Utc>::from_str("2023-01-02T23:00:00Z").unwrap(),
+            0,
+            None,
+            None,
+            "open".to_string(),
+        ));
+        let analysis = CorrelatedAnalysis {
+            sessions: sessions
+                .get_sessions()
+                .iter()
+                .cloned()
+                .map(CorrelatedSession::new)
+                .collect(),
+            orphans: OrphanEvents::default(),
+            cycles: Vec::new(),
+            app_start_counts_by_day: HashMap::new(),
+        };
+
+        let correlated = ReadingHeatmap::from_correlated(&analysis, &Utc);
+        assert_eq!(correlated.total_seconds(), 1200.0);
+        assert_eq!(
+            correlated.reading_seconds,
+            ReadingHeatmap::from_sessions(&sessions, &Utc).reading_seconds
+        );
+    }
+
+    #[test]
+    fn test_local_timezone_wraps_weekday() {
+        let mut heatmap = ReadingHeatmap::new();
+        let tz = FixedOffset::east_opt(3 * 3600).unwrap();
+        // Sunday 22:00 UTC is Monday 01:00 at UTC+3
+        heatmap.add_session(
+            &session("2023-01-01T22:00:00Z", "2023-01-01T22:20:00Z", 1200),
+            &tz,
+        );
+        assert_eq!(heatmap.reading_seconds[0][1], 1200.0);
+        assert_eq!(heatmap.reading_seconds[6][22], 0.0);
+    }
+}
@@ -1,4 +1,14 @@
+pub mod battery;
+pub mod brightness_stats;
+pub mod goals;
+pub mod heatmap;
 pub mod imp;
+pub mod timeseries;
 pub mod r#trait;
 
+pub use battery::BatteryModel;
+pub use brightness_stats::{BrightnessStats, MethodBreakdown};
+pub use goals::{Goal, GoalProgress, GoalsEngine, Streaks};
+pub use heatmap::ReadingHeatmap;
 pub use r#trait::Statistics;
+pub use timeseries::{Granularity, RollupMetrics, TimeSeries};
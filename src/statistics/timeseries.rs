@@ -0,0 +1,219 @@
+use chrono::{DateTime, Datelike, NaiveDate, TimeZone, Utc, Weekday};
+use std::collections::BTreeMap;
+
+use crate::{AppEventKind, CorrelatedAnalysis, ReadingSession, ReadingSessions};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Granularity {
+    Daily,
+    IsoWeekly,
+    Monthly,
+}
+
+impl Granularity {
+    pub fn bucket_start(&self, date: NaiveDate) -> NaiveDate {
+        match self {
+            Granularity::Daily => date,
+            Granularity::IsoWeekly => {
+                let week = date.iso_week();
+                NaiveDate::from_isoywd_opt(week.year(), week.week(), Weekday::Mon).unwrap_or(date)
+            }
+            Granularity::Monthly => date.with_day(1).unwrap_or(date),
+        }
+    }
+
+    pub fn label(&self, bucket_start: NaiveDate) -> String {
+        match self {
+            Granularity::Daily => bucket_start.format("%Y-%m-%d").to_string(),
+            Granularity::IsoWeekly => {
+                let week = bucket_start.iso_week();
+                format!("{}-W{:02}", week.year(), week.week())
+            }
+            Granularity::Monthly => bucket_start.format("%Y-%m").to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RollupMetrics {
+    pub seconds_read: u64,
+    pub pages_turned: u64,
+    pub sessions: usize,
+    pub dictionary_lookups: usize,
+    pub brightness_changes: usize,
+    pub app_starts: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct TimeSeries {
+    pub granularity: Granularity,
+    pub buckets: BTreeMap<NaiveDate, RollupMetrics>,
+}
+
+pub fn local_date<Tz: TimeZone>(ts: DateTime<Utc>, tz: &Tz) -> NaiveDate {
+    ts.with_timezone(tz).date_naive()
+}
+
+impl TimeSeries {
+    pub fn new(granularity: Granularity) -> Self {
+        Self {
+            granularity,
+            buckets: BTreeMap::new(),
+        }
+    }
+
+    pub fn from_sessions<Tz: TimeZone>(
+        sessions: &ReadingSessions,
+        granularity: Granularity,
+        tz: &Tz,
+    ) -> Self {
+        let mut series = Self::new(granularity);
+        for session in sessions.valid_sessions() {
+            series.add_session(session, tz);
+        }
+        series
+    }
+
+    pub fn from_correlated<Tz: TimeZone>(
+        analysis: &CorrelatedAnalysis,
+        granularity: Granularity,
+        tz: &Tz,
+    ) -> Self {
+        let mut series = Self::new(granularity);
+        for correlated in &analysis.sessions {
+            if correlated.session.is_valid() {
+                series.add_session(&correlated.session, tz);
+            }
+            series
+                .bucket_mut(correlated.session.time_start, tz)
+                .dictionary_lookups += correlated.dictionary.len();
+            for event in correlated
+                .brightness
+                .iter()
+                .chain(correlated.natural_light.iter())
+            {
+                series.bucket_mut(event.timestamp, tz).brightness_changes += 1;
+            }
+        }
+        for event in analysis
+            .orphans
+            .brightness
+            .iter()
+            .chain(analysis.orphans.natural_light.iter())
+        {
+            series.bucket_mut(event.timestamp, tz).brightness_changes += 1;
+        }
+        for event in analysis
+            .sessions
+            .iter()
+            .flat_map(|session| session.app_events.iter())
+            .chain(analysis.orphans.app_events.iter())
+            .filter(|event| event.kind == AppEventKind::AppStart)
+        {
+            series.bucket_mut(event.timestamp, tz).app_starts += 1;
+        }
+        series
+    }
+
+    pub fn get(&self, date: NaiveDate) -> Option<&RollupMetrics> {
+        self.buckets.get(&self.granularity.bucket_start(date))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (NaiveDate, &RollupMetrics)> {
+        self.buckets.iter().map(|(date, metrics)| (*date, metrics))
+    }
+
+    pub fn labeled(&self) -> Vec<(String, RollupMetrics)> {
+        self.iter()
+            .map(|(date, metrics)| (self.granularity.label(date), *metrics))
+            .collect()
+    }
+
+    fn add_session<Tz: TimeZone>(&mut self, session: &ReadingSession, tz: &Tz) {
+        let bucket = self.bucket_mut(session.time_start, tz);
+        bucket.sessions += 1;
+        bucket.seconds_read += session.seconds_read.unwrap_or(0);
+        bucket.pages_turned += session.pages_turned.unwrap_or(0);
+    }
+
+    fn bucket_mut<Tz: TimeZone>(&mut self, ts: DateTime<Utc>, tz: &Tz) -> &mut RollupMetrics {
+        let start = self.granularity.bucket_start(local_date(ts, tz));
+        self.buckets.entry(start).or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CorrelatedSession, OrphanEvents};
+    use chrono::FixedOffset;
+    use std::collections::HashMap;
+    use std::str::FromStr;
+
+    fn session(start: &str, end: &str, seconds: u64, pages: u64) -> ReadingSession {
+        let start = DateTime::<Utc>::from_str(start).unwrap();
+        let end = DateTime::<Utc>::from_str(end).unwrap();
+        let mut session = ReadingSession::new(start, 0, None, None, "open".to_string());
+        session
+            .complete_session(end, 10, 0, seconds, pages, "leave".to_string())
+            .unwrap();
+        session
+    }
+
+    fn analysis(sessions: Vec<ReadingSession>) -> CorrelatedAnalysis {
+        CorrelatedAnalysis {
+            sessions: sessions.into_iter().map(CorrelatedSession::new).collect(),
+            orphans: OrphanEvents::default(),
+            cycles: Vec::new(),
+            app_start_counts_by_day: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_daily_rollup_uses_local_timezone() {
+        let analysis = analysis(vec![session(
+            "2023-01-01T23:30:00Z",
+            "2023-01-01T23:50:00Z",
+            1200,
+            12,
+        )]);
+        let tz = FixedOffset::east_opt(2 * 3600).unwrap();
+
+        let utc = TimeSeries::from_correlated(&analysis, Granularity::Daily, &Utc);
+        let local = TimeSeries::from_correlated(&analysis, Granularity::Daily, &tz);
+
+        let jan1 = NaiveDate::from_ymd_opt(2023, 1, 1).unwrap();
+        let jan2 = NaiveDate::from_ymd_opt(2023, 1, 2).unwrap();
+        assert_eq!(utc.get(jan1).map(|m| m.seconds_read), Some(1200));
+        assert!(local.get(jan1).is_none());
+        assert_eq!(local.get(jan2).map(|m| m.pages_turned), Some(12));
+    }
+
+    #[test]
+    fn test_weekly_and_monthly_buckets() {
+        let partial = ReadingSession::new(
+            DateTime::<Utc>::from_str("2023-01-31T10:00:00Z").unwrap(),
+            0,
+            None,
+            None,
+            "open".to_string(),
+        );
+        let analysis = analysis(vec![
+            session("2023-01-30T10:00:00Z", "2023-01-30T10:10:00Z", 600, 5),
+            session("2023-01-31T09:00:00Z", "2023-01-31T09:00:30Z", 30, 1),
+            partial,
+            session("2023-02-01T10:00:00Z", "2023-02-01T10:10:00Z", 300, 2),
+        ]);
+
+        let weekly = TimeSeries::from_correlated(&analysis, Granularity::IsoWeekly, &Utc);
+        assert_eq!(weekly.buckets.len(), 1);
+        let labeled = weekly.labeled();
+        assert_eq!(labeled[0].0, "2023-W05");
+        assert_eq!(labeled[0].1.sessions, 2);
+        assert_eq!(labeled[0].1.seconds_read, 900);
+
+        let monthly = TimeSeries::from_correlated(&analysis, Granularity::Monthly, &Utc);
+        let labels: Vec<String> = monthly.labeled().into_iter().map(|(l, _)| l).collect();
+        assert_eq!(labels, vec!["2023-01", "2023-02"]);
+    }
+}